@@ -1,7 +1,7 @@
 use numerical_value::*;
 use std::cmp::Ordering;
 use std::fmt;
-use std::ops::{Add,Sub,Mul,Div,Rem,Neg};
+use std::ops::{Add,Sub,Mul,Div,Rem,Neg,Not,BitAnd,BitOr,BitXor,Shl,Shr};
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub enum BoundedValue<T> {
@@ -11,6 +11,7 @@ pub enum BoundedValue<T> {
 }
 
 impl<T> BoundedValue<T> {
+    #[allow(dead_code)]
     pub fn map<U, F>(self, f: F) -> BoundedValue<U> where F: FnOnce(T) -> U {
         use self::BoundedValue::*;
         match self {
@@ -22,6 +23,7 @@ impl<T> BoundedValue<T> {
 }
 
 impl<T> BoundedValue<T> where T: MinMax {
+    #[allow(dead_code)]
     pub fn unwrap(self) -> T {
         use self::BoundedValue::*;
         match self {
@@ -32,20 +34,58 @@ impl<T> BoundedValue<T> where T: MinMax {
     }
 }
 
-impl Add for BoundedValue<i64> {
-    type Output = Self;
-    fn add(self, other: Self) -> Self {
+impl BoundedValue<i64> {
+    /// Adds the two values, returning `None` if the sum is indeterminate
+    /// (`-inf + inf`).
+    pub fn checked_add(self, other: Self) -> Option<Self> {
         use self::BoundedValue::*;
         match (self, other) {
-            (Min, Min) => Min,
-            (Min, Raw(_)) => Min,
-            (Min, Max) => unimplemented!(),
-            (Max, Min) => unimplemented!(),
-            (Max, Raw(_)) => Max,
-            (Max, Max) => Max,
-            (Raw(_), Min) => Min,
-            (Raw(a), Raw(b)) => Raw(a + b),
-            (Raw(_), Max) => Max,
+            (Min, Min) => Some(Min),
+            (Min, Raw(_)) => Some(Min),
+            (Min, Max) => None,
+            (Max, Min) => None,
+            (Max, Raw(_)) => Some(Max),
+            (Max, Max) => Some(Max),
+            (Raw(_), Min) => Some(Min),
+            (Raw(a), Raw(b)) => Some(match a.checked_add(b) {
+                Some(c) => Raw(c),
+                None => if a > 0 { Max } else { Min },
+            }),
+            (Raw(_), Max) => Some(Max),
+        }
+    }
+
+    /// Adds a finite `n`, which unlike adding another bound always has a
+    /// result.
+    pub fn offset(self, n: i64) -> Self {
+        use self::BoundedValue::*;
+        match self {
+            Raw(a) => match a.checked_add(n) {
+                Some(c) => Raw(c),
+                None => if n > 0 { Max } else { Min },
+            },
+            bound => bound,
+        }
+    }
+
+    /// Divides the two values, returning `None` if `other` is zero.
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        use self::BoundedValue::*;
+        match (self, other) {
+            (_, Raw(0)) => None,
+            (Min, Min) => Some(Max),
+            (Min, Raw(b)) => Some(if b > 0 { Min } else { Max }),
+            (Min, Max) => Some(Min),
+            (Max, Min) => Some(Min),
+            (Max, Raw(b)) => Some(if b > 0 { Max } else { Min }),
+            (Max, Max) => Some(Max),
+            (Raw(_), Min) => Some(Raw(0)),
+            (Raw(a), Raw(b)) => Some(match a.checked_div(b) {
+                Some(c) => Raw(c),
+                // i64::MIN / -1
+                None => Max,
+            }),
+            (Raw(_), Max) => Some(Raw(0)),
         }
     }
 }
@@ -55,14 +95,14 @@ impl Mul for BoundedValue<i64> {
     fn mul(self, other: Self) -> Self {
         use self::BoundedValue::*;
         match (self, other) {
-            (Min, Min) => Min,
+            (Min, Min) => Max,
             (Min, Raw(b)) => {
                 if b > 0 { Min }
                 else if b == 0 { Raw(b) }
                 else { Max }
             },
-            (Min, Max) => unimplemented!(),
-            (Max, Min) => unimplemented!(),
+            (Min, Max) => Min,
+            (Max, Min) => Min,
             (Max, Raw(b)) => {
                 if b > 0 { Max }
                 else if b == 0 { Raw(b) }
@@ -74,7 +114,10 @@ impl Mul for BoundedValue<i64> {
                 else if a == 0 { Raw(a) }
                 else { Max }
             },
-            (Raw(a), Raw(b)) => Raw(a * b),
+            (Raw(a), Raw(b)) => match a.checked_mul(b) {
+                Some(c) => Raw(c),
+                None => if (a > 0) == (b > 0) { Max } else { Min },
+            },
             (Raw(a), Max) => {
                 if a > 0 { Max }
                 else if a == 0 { Raw(a) }
@@ -84,51 +127,41 @@ impl Mul for BoundedValue<i64> {
     }
 }
 
-impl Div for BoundedValue<i64> {
+impl Neg for BoundedValue<i64> {
     type Output = Self;
-    fn div(self, other: Self) -> Self {
+    fn neg(self) -> Self {
         use self::BoundedValue::*;
-        match (self, other) {
-            (Min, Min) => Max,
-            (Min, Raw(b)) => {
-                if b > 0 { Min }
-                else if b == 0 { unimplemented!() }
-                else { Max }
-            },
-            (Min, Max) => Min,
-            (Max, Min) => Min,
-            (Max, Raw(b)) => {
-                if b > 0 { Max }
-                else if b == 0 { unimplemented!() }
-                else { Min }
-            },
-            (Max, Max) => Max,
-            (Raw(_), Min) => Raw(0),
-            (Raw(a), Raw(b)) => Raw(a / b),
-            (Raw(_), Max) => Raw(0),
+        match self {
+            Min => Max,
+            Raw(t) => t.checked_neg().map_or(Max, Raw),
+            Max => Min,
         }
     }
 }
 
-impl Neg for BoundedValue<i64> {
+impl Not for BoundedValue<i64> {
     type Output = Self;
-    fn neg(self) -> Self {
+    fn not(self) -> Self {
         use self::BoundedValue::*;
         match self {
             Min => Max,
-            Raw(t) => Raw(-t),
+            Raw(t) => Raw(!t),
             Max => Min,
         }
     }
 }
 
 impl BoundedValue<i64> {
-    pub fn abs(self) -> Self {
+    /// The smallest `2^k - 1` that is at least `self`, which must not be
+    /// negative.
+    fn bit_ceiling(self) -> Self {
         use self::BoundedValue::*;
         match self {
-            Min => Max,
-            Raw(t) => Raw(t.abs()),
-            Max => Max,
+            Raw(t) => {
+                let bits = 64 - t.leading_zeros();
+                if bits >= 63 { Raw(i64::MAX) } else { Raw((1 << bits) - 1) }
+            },
+            _ => self,
         }
     }
 }
@@ -196,32 +229,35 @@ impl Add for Range<BoundedValue<i64>> {
     type Output = Self;
     fn add(self, other: Self) -> Self {
         use Inclusivity::*;
+        // -inf + inf can be anything, so widen that bound all the way.
+        let min = |a: BoundedValue<i64>, b| a.checked_add(b).unwrap_or(BoundedValue::Min);
+        let max = |a: BoundedValue<i64>, b| a.checked_add(b).unwrap_or(BoundedValue::Max);
         Range {
             min: match (self.min.inclusivity, other.min.inclusivity) {
                 (Inclusive, Inclusive) => MinPair {
-                    value: self.min.value.add(other.min.value),
+                    value: min(self.min.value, other.min.value),
                     inclusivity: Inclusive,
                 },
                 (Exclusive, Exclusive) => MinPair {
-                    value: self.min.value.add(other.min.value.map(|r| r + 1)),
+                    value: min(self.min.value, other.min.value.offset(1)),
                     inclusivity: Exclusive,
                 },
                 (_, _) => MinPair {
-                    value: self.min.value.add(other.min.value),
+                    value: min(self.min.value, other.min.value),
                     inclusivity: Exclusive,
                 },
             },
             max: match (self.max.inclusivity, other.max.inclusivity) {
                 (Inclusive, Inclusive) => MaxPair {
-                    value: self.max.value.add(other.max.value),
+                    value: max(self.max.value, other.max.value),
                     inclusivity: Inclusive,
                 },
                 (Exclusive, Exclusive) => MaxPair {
-                    value: self.max.value.add(other.max.value.map(|r| r - 1)),
+                    value: max(self.max.value, other.max.value.offset(-1)),
                     inclusivity: Exclusive,
                 },
                 (_, _) => MaxPair {
-                    value: self.max.value.add(other.max.value),
+                    value: max(self.max.value, other.max.value),
                     inclusivity: Exclusive,
                 },
             },
@@ -232,112 +268,104 @@ impl Add for Range<BoundedValue<i64>> {
 impl Sub for Range<BoundedValue<i64>> {
     type Output = Self;
     fn sub(self, other: Self) -> Self {
-        self + Range::new(-other.max.value, other.max.inclusivity,
-                          -other.min.value, other.min.inclusivity)
+        self + -other
+    }
+}
+
+impl Neg for Range<BoundedValue<i64>> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Range::new(-self.max.value, self.max.inclusivity,
+                   -self.min.value, self.min.inclusivity)
+    }
+}
+
+/// Bitwise not, ie `-x - 1`.
+impl Not for Range<BoundedValue<i64>> {
+    type Output = Self;
+    fn not(self) -> Self {
+        let one = BoundedValue::Raw(1);
+        -self - Range::new(one, Inclusivity::Inclusive, one, Inclusivity::Inclusive)
+    }
+}
+
+impl Range<BoundedValue<i64>> {
+    /// The same set of integers with both bounds inclusive.  Infinite bounds
+    /// are left as they are.
+    pub fn inclusive(self) -> Self {
+        use self::BoundedValue::*;
+        use Inclusivity::*;
+        let min = match (self.min.value, self.min.inclusivity) {
+            (Raw(v), Exclusive) => Raw(v).offset(1),
+            (v, _) => v,
+        };
+        let max = match (self.max.value, self.max.inclusivity) {
+            (Raw(v), Exclusive) => Raw(v).offset(-1),
+            (v, _) => v,
+        };
+        Range::new(min, Inclusive, max, Inclusive)
     }
 }
 
 impl Mul for Range<BoundedValue<i64>> {
     type Output = Self;
     fn mul(self, other: Self) -> Self {
-        use Inclusivity::*;
-        Range {
-            min: match (self.min.inclusivity, other.min.inclusivity) {
-                (Inclusive, Inclusive) => MinPair {
-                    value: self.min.value.mul(other.min.value),
-                    inclusivity: Inclusive,
-                },
-                (Exclusive, Exclusive) => MinPair {
-                    value: self.min.value.mul(other.min.value.map(|r| r + 1)),
-                    inclusivity: Exclusive,
-                },
-                (_, _) => MinPair {
-                    value: self.min.value.mul(other.min.value),
-                    inclusivity: Exclusive,
-                },
-            },
-            max: match (self.max.inclusivity, other.max.inclusivity) {
-                (Inclusive, Inclusive) => MaxPair {
-                    value: self.max.value.mul(other.max.value),
-                    inclusivity: Inclusive,
-                },
-                (Exclusive, Exclusive) => MaxPair {
-                    value: self.max.value.mul(other.max.value.map(|r| r - 1)),
-                    inclusivity: Exclusive,
-                },
-                (_, _) => MaxPair {
-                    value: self.max.value.mul(other.max.value),
-                    inclusivity: Exclusive,
-                },
-            },
-        }
+        // With signs involved any corner can be the smallest or largest
+        // product, eg [-3, 2] * [-5, 1] = [-10, 15].
+        let a = self.inclusive();
+        let b = other.inclusive();
+        let corners = [a.min.value * b.min.value, a.min.value * b.max.value,
+                       a.max.value * b.min.value, a.max.value * b.max.value];
+        Range::new(*corners.iter().min().unwrap(), Inclusivity::Inclusive,
+                   *corners.iter().max().unwrap(), Inclusivity::Inclusive)
     }
 }
 
 impl Div for Range<BoundedValue<i64>> {
     type Output = Self;
     fn div(self, other: Self) -> Self {
-        use Inclusivity::*;
-        if other.min == other.max {
-            Range::new(self.min.value.div(other.min.value), self.min.inclusivity,
-                       self.max.value.div(other.max.value), self.max.inclusivity)
+        use self::BoundedValue::*;
+        // Split the divisor into its negative and positive parts.  Over
+        // either part the quotient only moves one way in each operand, so
+        // the corners bound it, eg [1, 5] / -1 = [-5, -1].
+        let a = self.inclusive();
+        let b = other.inclusive();
+        let mut parts = Vec::new();
+        if b.min.value < 0 {
+            parts.push((b.min.value, ::std::cmp::min(b.max.value, Raw(-1))));
+        }
+        if b.max.value > 0 {
+            parts.push((::std::cmp::max(b.min.value, Raw(1)), b.max.value));
+        }
+        let corners: Vec<BoundedValue<i64>> = parts.into_iter()
+            .flat_map(|(min, max)| vec![(a.min.value, min), (a.min.value, max),
+                                        (a.max.value, min), (a.max.value, max)])
+            .filter_map(|(a, b)| a.checked_div(b))
+            .collect();
+        match (corners.iter().min(), corners.iter().max()) {
+            (Some(&min), Some(&max)) =>
+                Range::new(min, Inclusivity::Inclusive, max, Inclusivity::Inclusive),
+            // The divisor can only be zero.
+            _ => Range::universe(),
+        }
+    }
+}
+
+impl Range<BoundedValue<i64>> {
+    /// The smallest and largest absolute value of the non zero elements, or
+    /// `None` if zero is the only element.
+    pub fn nonzero_magnitude(self) -> Option<(BoundedValue<i64>, BoundedValue<i64>)> {
+        use self::BoundedValue::*;
+        let range = self.inclusive();
+        let (min, max) = (range.min.value, range.max.value);
+        if min > 0 {
+            Some((min, max))
+        } else if max < 0 {
+            Some((-max, -min))
+        } else if min == 0 && max == 0 {
+            None
         } else {
-            let biggest_negative; // most negative (ie -inf)
-            let smallest_negative; // least negative (ie -1)
-            let smallest_positive; // least positive (ie 1)
-            let biggest_positive; // most positive (ie inf)
-            if other.min.value < 0 {
-                biggest_negative = Some(other.min.value);
-                if other.max.value >= -1 {
-                    smallest_negative = Some(BoundedValue::Raw(-1));
-                } else {
-                    smallest_negative = Some(other.max.value);
-                }
-            } else {
-                biggest_negative = None;
-                smallest_negative = None;
-            }
-            if other.max.value > 0 {
-                biggest_positive = Some(other.max.value);
-                if other.min.value <= 1 {
-                    smallest_positive = Some(1.into());
-                } else {
-                    smallest_positive = Some(other.min.value);
-                }
-            } else {
-                biggest_positive = None;
-                smallest_positive = None;
-            }
-            println!("{:?} {:?} {:?} {:?}", biggest_negative, smallest_negative, smallest_positive, biggest_positive);
-            match (biggest_negative, smallest_negative, smallest_positive, biggest_positive) {
-                (_, Some(n), Some(p), _) => {
-                    // 4 / [-2, 4] = [-4, 4]
-                    // [4, 8] / [-2, 4] = [-8, 8]
-                    // [-8, -4] / [-2, 4] = [-8, 8]
-                    // [-8, 4] / [-2, 4] = [-8, 8]
-                    // [-4, 8] / [-2, 4] = [-8, 8]
-                    if self.min.value > 0 && self.max.value > 0 {
-                        Range::new(self.max.value.div(n), Inclusive, self.max.value.div(p), Inclusive)
-                    } else if self.max.value < 0 && self.max.value < 0 {
-                        Range::new(self.min.value.div(p), Inclusive, self.min.value.div(n), Inclusive)
-                    } else if self.min.value.abs() < self.max.value {
-                        Range::new(self.max.value.div(n), Inclusive, self.max.value.div(p), Inclusive)
-                    } else {
-                        Range::new(self.min.value.div(p), Inclusive, self.min.value.div(n), Inclusive)
-                    }
-                },
-                (Some(b), Some(s), _, _) =>
-                // 4 / [-4, -2] = [-2, -1]
-                // [4, 8] / [-4, -2] = [-4, -1]
-                    Range::new(self.max.value.div(s), Inclusive, self.min.value.div(b), Inclusive),
-                (_, _, Some(s), Some(b)) =>
-                // 4 / [2, 4] = [1, 2]
-                // [4, 8] / [2, 4] = [1, 4]
-                // 32 / [4, inf] = [0, 8]
-                    Range::new(self.min.value.div(b), Inclusive, self.max.value.div(s), Inclusive),
-                (None, None, None, None) => unimplemented!(),
-                _ => unreachable!(),
-            }
+            Some((Raw(1), ::std::cmp::max(-min, max)))
         }
     }
 }
@@ -345,7 +373,142 @@ impl Div for Range<BoundedValue<i64>> {
 impl Rem for Range<BoundedValue<i64>> {
     type Output = Self;
     fn rem(self, other: Self) -> Self {
-        other
+        use self::BoundedValue::*;
+        use Inclusivity::*;
+        // C truncates, so the remainder has the sign of the dividend and is
+        // smaller in magnitude than both the dividend and the divisor.
+        let (smallest, largest) = match other.nonzero_magnitude() {
+            Some(magnitude) => magnitude,
+            None => return Range::universe(),
+        };
+        let dividend = self.inclusive();
+        let (min, max) = (dividend.min.value, dividend.max.value);
+        if min > -smallest && max < smallest {
+            // Every dividend is already smaller than every divisor.
+            return dividend;
+        }
+        let limit = largest.offset(-1);
+        Range::new(if min < 0 { ::std::cmp::max(min, -limit) } else { Raw(0) }, Inclusive,
+                   if max > 0 { ::std::cmp::min(max, limit) } else { Raw(0) }, Inclusive)
+    }
+}
+
+impl Range<BoundedValue<i64>> {
+    /// The smallest `[-2^k, 2^k - 1]` containing both ranges.  Bitwise
+    /// operations on values in it stay in it.
+    fn bit_hull(self, other: Self) -> (BoundedValue<i64>, BoundedValue<i64>) {
+        use self::BoundedValue::*;
+        let (a, b) = (self.inclusive(), other.inclusive());
+        let largest = *[a.max.value, b.max.value, !a.min.value, !b.min.value, Raw(0)]
+            .iter().max().unwrap();
+        let ceiling = largest.bit_ceiling();
+        (!ceiling, ceiling)
+    }
+}
+
+impl BitAnd for Range<BoundedValue<i64>> {
+    type Output = Self;
+    fn bitand(self, other: Self) -> Self {
+        use self::BoundedValue::*;
+        use Inclusivity::*;
+        let (floor, _) = self.bit_hull(other);
+        let (a, b) = (self.inclusive(), other.inclusive());
+        // x & y <= x if x isn't negative, and x & y <= min(x, y) if they
+        // have the same sign.
+        let (min, max) = if a.min.value >= 0 && b.min.value >= 0 {
+            (Raw(0), ::std::cmp::min(a.max.value, b.max.value))
+        } else if a.min.value >= 0 {
+            (Raw(0), a.max.value)
+        } else if b.min.value >= 0 {
+            (Raw(0), b.max.value)
+        } else if a.max.value < 0 && b.max.value < 0 {
+            (floor, ::std::cmp::min(a.max.value, b.max.value))
+        } else {
+            (floor, ::std::cmp::max(a.max.value, b.max.value))
+        };
+        Range::new(min, Inclusive, max, Inclusive)
+    }
+}
+
+impl BitOr for Range<BoundedValue<i64>> {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        // x | y == ~(~x & ~y)
+        !(!self & !other)
+    }
+}
+
+impl BitXor for Range<BoundedValue<i64>> {
+    type Output = Self;
+    fn bitxor(self, other: Self) -> Self {
+        use self::BoundedValue::*;
+        use Inclusivity::*;
+        let (floor, ceiling) = self.bit_hull(other);
+        let (a, b) = (self.inclusive(), other.inclusive());
+        let positive = |r: Self| r.min.value >= 0;
+        let negative = |r: Self| r.max.value < 0;
+        // The sign bit is set if exactly one of them is negative
+        if (positive(a) && positive(b)) || (negative(a) && negative(b)) {
+            Range::new(Raw(0), Inclusive, ceiling, Inclusive)
+        } else if (positive(a) && negative(b)) || (negative(a) && positive(b)) {
+            Range::new(floor, Inclusive, Raw(-1), Inclusive)
+        } else {
+            Range::new(floor, Inclusive, ceiling, Inclusive)
+        }
+    }
+}
+
+impl Range<BoundedValue<i64>> {
+    /// The shift amounts in `self` that are defined for an `i64`, or `None`
+    /// if there aren't any.
+    fn shift_amounts(self) -> Option<(u32, u32)> {
+        use self::BoundedValue::*;
+        let range = self.inclusive();
+        let min = ::std::cmp::max(range.min.value, Raw(0));
+        let max = ::std::cmp::min(range.max.value, Raw(63));
+        match (min, max) {
+            (Raw(min), Raw(max)) if min <= max => Some((min as u32, max as u32)),
+            _ => None,
+        }
+    }
+}
+
+impl Shl for Range<BoundedValue<i64>> {
+    type Output = Self;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn shl(self, other: Self) -> Self {
+        use self::BoundedValue::*;
+        use Inclusivity::*;
+        let (min, max) = match other.shift_amounts() {
+            Some(amounts) => amounts,
+            None => return Range::universe(),
+        };
+        // x << s == x * 2^s
+        let power = |s: u32| if s >= 63 { Max } else { Raw(1 << s) };
+        self * Range::new(power(min), Inclusive, power(max), Inclusive)
+    }
+}
+
+impl Shr for Range<BoundedValue<i64>> {
+    type Output = Self;
+    fn shr(self, other: Self) -> Self {
+        use self::BoundedValue::*;
+        use Inclusivity::*;
+        let (min, max) = match other.shift_amounts() {
+            Some(amounts) => amounts,
+            None => return Range::universe(),
+        };
+        // Shifting moves values towards 0 or -1, so the extremes come from
+        // the ends of `self` shifted by the least or most.
+        let shift = |value: BoundedValue<i64>, s: u32| match value {
+            Raw(t) => Raw(t >> s),
+            _ => value,
+        };
+        let range = self.inclusive();
+        Range::new(::std::cmp::min(shift(range.min.value, min), shift(range.min.value, max)),
+                   Inclusive,
+                   ::std::cmp::max(shift(range.max.value, min), shift(range.max.value, max)),
+                   Inclusive)
     }
 }
 
@@ -356,8 +519,91 @@ mod tests {
 
     #[test]
     fn div_1() {
-        assert_eq!(Raw(4) / Raw(2), Raw(2));
-        assert_eq!(Raw(4) / Min, Raw(0));
+        assert_eq!(Raw(4).checked_div(Raw(2)), Some(Raw(2)));
+        assert_eq!(Raw(4).checked_div(Min), Some(Raw(0)));
+    }
+
+    #[test]
+    fn add_arms() {
+        assert_eq!(Min.checked_add(Min), Some(Min));
+        assert_eq!(Min.checked_add(Raw(3)), Some(Min));
+        assert_eq!(Min.checked_add(Max), None);
+        assert_eq!(Max.checked_add(Min), None);
+        assert_eq!(Max.checked_add(Raw(3)), Some(Max));
+        assert_eq!(Max.checked_add(Max), Some(Max));
+        assert_eq!(Raw(3).checked_add(Min), Some(Min));
+        assert_eq!(Raw(3).checked_add(Raw(4)), Some(Raw(7)));
+        assert_eq!(Raw(3).checked_add(Max), Some(Max));
+    }
+
+    #[test]
+    fn mul_arms() {
+        assert_eq!(Min * Min, Max);
+        assert_eq!(Min * Raw(3), Min);
+        assert_eq!(Min * Raw(0), Raw(0));
+        assert_eq!(Min * Raw(-3), Max);
+        assert_eq!(Min * Max, Min);
+        assert_eq!(Max * Min, Min);
+        assert_eq!(Max * Raw(3), Max);
+        assert_eq!(Max * Raw(0), Raw(0));
+        assert_eq!(Max * Raw(-3), Min);
+        assert_eq!(Max * Max, Max);
+        assert_eq!(Raw(3) * Min, Min);
+        assert_eq!(Raw(0) * Min, Raw(0));
+        assert_eq!(Raw(-3) * Min, Max);
+        assert_eq!(Raw(3) * Raw(-4), Raw(-12));
+        assert_eq!(Raw(3) * Max, Max);
+        assert_eq!(Raw(0) * Max, Raw(0));
+        assert_eq!(Raw(-3) * Max, Min);
+    }
+
+    #[test]
+    fn div_arms() {
+        assert_eq!(Min.checked_div(Min), Some(Max));
+        assert_eq!(Min.checked_div(Raw(3)), Some(Min));
+        assert_eq!(Min.checked_div(Raw(0)), None);
+        assert_eq!(Min.checked_div(Raw(-3)), Some(Max));
+        assert_eq!(Min.checked_div(Max), Some(Min));
+        assert_eq!(Max.checked_div(Min), Some(Min));
+        assert_eq!(Max.checked_div(Raw(3)), Some(Max));
+        assert_eq!(Max.checked_div(Raw(0)), None);
+        assert_eq!(Max.checked_div(Raw(-3)), Some(Min));
+        assert_eq!(Max.checked_div(Max), Some(Max));
+        assert_eq!(Raw(3).checked_div(Min), Some(Raw(0)));
+        assert_eq!(Raw(12).checked_div(Raw(-4)), Some(Raw(-3)));
+        assert_eq!(Raw(12).checked_div(Raw(0)), None);
+        assert_eq!(Raw(3).checked_div(Max), Some(Raw(0)));
+    }
+
+    #[test]
+    fn overflow_add() {
+        assert_eq!(Raw(i64::MAX).checked_add(Raw(1)), Some(Max));
+        assert_eq!(Raw(i64::MIN).checked_add(Raw(-1)), Some(Min));
+        assert_eq!(Raw(i64::MAX).checked_add(Raw(i64::MIN)), Some(Raw(-1)));
+        assert_eq!(Raw(i64::MAX).offset(1), Max);
+        assert_eq!(Raw(i64::MIN).offset(-1), Min);
+        assert_eq!(Min.offset(1), Min);
+        assert_eq!(Max.offset(-1), Max);
+    }
+
+    #[test]
+    fn overflow_mul() {
+        assert_eq!(Raw(i64::MAX) * Raw(2), Max);
+        assert_eq!(Raw(i64::MAX) * Raw(-2), Min);
+        assert_eq!(Raw(i64::MIN) * Raw(2), Min);
+        assert_eq!(Raw(i64::MIN) * Raw(-1), Max);
+        assert_eq!(Raw(1 << 32) * Raw(1 << 32), Max);
+    }
+
+    #[test]
+    fn overflow_div() {
+        assert_eq!(Raw(i64::MIN).checked_div(Raw(-1)), Some(Max));
+    }
+
+    #[test]
+    fn overflow_neg() {
+        assert_eq!(-Raw(i64::MIN), Max);
+        assert_eq!(-Raw(i64::MAX), Raw(-i64::MAX));
     }
 }
 
@@ -456,10 +702,265 @@ mod operator_tests {
             Range::new(Raw(0), Inclusive, Raw(8), Inclusive));
     }
 
+    #[test]
+    fn div_11() {
+        assert_eq!(
+            // [1, 5] / -1 = [-5, -1]
+            Range::new(Raw(1), Inclusive, Raw(5), Inclusive) / Range::from(Raw(-1)),
+            Range::new(Raw(-5), Inclusive, Raw(-1), Inclusive));
+    }
+
+    #[test]
+    fn add_overflow() {
+        assert_eq!(
+            Range::new(Raw(0), Inclusive, Raw(i64::MAX), Inclusive) + Range::from(Raw(1)),
+            Range::new(Raw(1), Inclusive, Max, Inclusive));
+        assert_eq!(
+            Range::new(Raw(0), Inclusive, Raw(i64::MAX), Exclusive) +
+                Range::new(Raw(0), Inclusive, Raw(i64::MAX), Exclusive),
+            Range::new(Raw(0), Inclusive, Max, Exclusive));
+        assert_eq!(
+            Range::new(Raw(i64::MIN), Exclusive, Raw(0), Inclusive) -
+                Range::new(Raw(0), Inclusive, Raw(i64::MAX), Inclusive),
+            Range::new(Min, Exclusive, Raw(0), Inclusive));
+    }
+
+    #[test]
+    fn add_indeterminate() {
+        assert_eq!(
+            Range::new(Min, Inclusive, Raw(0), Inclusive) + Range::from(Max),
+            Range::new(Min, Inclusive, Max, Inclusive));
+        assert_eq!(
+            Range::from(Min) + Range::new(Raw(0), Inclusive, Max, Inclusive),
+            Range::new(Min, Inclusive, Max, Inclusive));
+        assert_eq!(
+            Range::universe() - Range::universe(),
+            Range::universe());
+    }
+
+    #[test]
+    fn div_by_zero() {
+        assert_eq!(
+            Range::from(Raw(32)) / Range::from(Raw(0)),
+            Range::universe());
+        assert_eq!(
+            Range::new(Raw(4), Inclusive, Raw(8), Inclusive) /
+                Range::new(Raw(0), Inclusive, Raw(0), Exclusive),
+            Range::universe());
+    }
+
+    #[test]
+    fn mul_1() {
+        assert_eq!(
+            Range::new(Raw(-3), Inclusive, Raw(2), Inclusive) *
+                Range::new(Raw(-5), Inclusive, Raw(1), Inclusive),
+            Range::new(Raw(-10), Inclusive, Raw(15), Inclusive));
+    }
+
+    #[test]
+    fn mul_2() {
+        assert_eq!(
+            Range::new(Raw(-3), Exclusive, Raw(2), Exclusive) *
+                Range::new(Raw(-5), Inclusive, Raw(1), Inclusive),
+            Range::new(Raw(-5), Inclusive, Raw(10), Inclusive));
+    }
+
+    #[test]
+    fn mul_3() {
+        assert_eq!(
+            Range::new(Min, Inclusive, Raw(-1), Inclusive) *
+                Range::new(Raw(-2), Inclusive, Raw(-1), Inclusive),
+            Range::new(Raw(1), Inclusive, Max, Inclusive));
+        assert_eq!(
+            Range::universe() * Range::from(Raw(0)),
+            Range::from(Raw(0)));
+        assert_eq!(
+            Range::new(Raw(0), Exclusive, Max, Inclusive) *
+                Range::new(Min, Inclusive, Raw(0), Exclusive),
+            Range::new(Min, Inclusive, Raw(-1), Inclusive));
+    }
+
+    fn small_ranges() -> Vec<Range<BoundedValue<i64>>> {
+        let mut ranges = Vec::new();
+        for min in -4..5 {
+            for max in min..5 {
+                for &min_i in &[Inclusive, Exclusive] {
+                    for &max_i in &[Inclusive, Exclusive] {
+                        let range = Range::new(Raw(min), min_i, Raw(max), max_i);
+                        if !values(range).is_empty() {
+                            ranges.push(range);
+                        }
+                    }
+                }
+            }
+        }
+        ranges
+    }
+
+    fn values(range: Range<BoundedValue<i64>>) -> Vec<i64> {
+        let range = range.inclusive();
+        (range.min.value.unwrap()..range.max.value.unwrap() + 1).collect()
+    }
+
+    /// Checks that `op` on ranges gives exactly the smallest and largest
+    /// results of `f` on every pair of values in the ranges.
+    fn check_brute_force<F, G>(op: F, f: G)
+        where F: Fn(Range<BoundedValue<i64>>, Range<BoundedValue<i64>>) -> Range<BoundedValue<i64>>,
+              G: Fn(i64, i64) -> Option<i64> {
+        let ranges = small_ranges();
+        for &a in &ranges {
+            for &b in &ranges {
+                let results: Vec<i64> = values(a).into_iter()
+                    .flat_map(|x| values(b).into_iter().filter_map(|y| f(x, y)).collect::<Vec<_>>())
+                    .collect();
+                if results.is_empty() {
+                    continue;
+                }
+                let expected = Range::new(Raw(*results.iter().min().unwrap()), Inclusive,
+                                          Raw(*results.iter().max().unwrap()), Inclusive);
+                assert_eq!(op(a, b).inclusive(), expected, "{:?} and {:?}", a, b);
+            }
+        }
+    }
+
+    /// Like `check_brute_force`, but only requires the range to contain
+    /// every result.
+    fn check_brute_force_sound<F, G>(op: F, f: G)
+        where F: Fn(Range<BoundedValue<i64>>, Range<BoundedValue<i64>>) -> Range<BoundedValue<i64>>,
+              G: Fn(i64, i64) -> Option<i64> {
+        let ranges = small_ranges();
+        for &a in &ranges {
+            for &b in &ranges {
+                let result = op(a, b).inclusive();
+                for x in values(a) {
+                    for y in values(b) {
+                        if let Some(z) = f(x, y) {
+                            assert!(result.min.value <= z && result.max.value >= z,
+                                    "{:?} and {:?} gave {:?} which excludes {}", a, b, result, z);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mul_brute_force() {
+        check_brute_force(|a, b| a * b, |x, y| Some(x * y));
+    }
+
+    #[test]
+    fn div_brute_force() {
+        check_brute_force_sound(|a, b| a / b, |x, y| if y == 0 { None } else { Some(x / y) });
+    }
+
     #[test]
     fn mod_1() {
         assert_eq!(
             Range::universe() % Range::new(Raw(0), Inclusive, Raw(4), Inclusive),
-            Range::new(Raw(0), Inclusive, Raw(4), Inclusive));
+            Range::new(Raw(-3), Inclusive, Raw(3), Inclusive));
+    }
+
+    #[test]
+    fn mod_2() {
+        assert_eq!(
+            Range::universe() % Range::new(Raw(1), Inclusive, Raw(4), Inclusive),
+            Range::new(Raw(-3), Inclusive, Raw(3), Inclusive));
+        assert_eq!(
+            Range::new(Raw(0), Inclusive, Raw(10), Inclusive) % Range::from(Raw(4)),
+            Range::new(Raw(0), Inclusive, Raw(3), Inclusive));
+        assert_eq!(
+            Range::new(Raw(0), Inclusive, Raw(10), Inclusive) % Range::from(Raw(-4)),
+            Range::new(Raw(0), Inclusive, Raw(3), Inclusive));
+        assert_eq!(
+            Range::new(Raw(-10), Inclusive, Raw(-2), Inclusive) % Range::from(Raw(4)),
+            Range::new(Raw(-3), Inclusive, Raw(0), Inclusive));
+    }
+
+    #[test]
+    fn mod_3() {
+        // Exact when the dividend is smaller than the divisor
+        assert_eq!(
+            Range::new(Raw(2), Inclusive, Raw(5), Exclusive) % Range::new(Raw(8), Inclusive, Max, Inclusive),
+            Range::new(Raw(2), Inclusive, Raw(4), Inclusive));
+        assert_eq!(
+            Range::new(Raw(-7), Inclusive, Raw(7), Inclusive) % Range::new(Min, Inclusive, Raw(-8), Inclusive),
+            Range::new(Raw(-7), Inclusive, Raw(7), Inclusive));
+        // Bounded by the dividend
+        assert_eq!(
+            Range::new(Raw(-2), Inclusive, Raw(20), Inclusive) % Range::from(Raw(8)),
+            Range::new(Raw(-2), Inclusive, Raw(7), Inclusive));
+    }
+
+    #[test]
+    fn mod_by_zero() {
+        assert_eq!(
+            Range::from(Raw(32)) % Range::from(Raw(0)),
+            Range::universe());
+    }
+
+    #[test]
+    fn mod_brute_force() {
+        check_brute_force_sound(|a, b| a % b, |x, y| if y == 0 { None } else { Some(x % y) });
+    }
+
+    #[test]
+    fn neg_1() {
+        assert_eq!(-Range::new(Raw(-3), Inclusive, Max, Inclusive),
+                   Range::new(Min, Inclusive, Raw(3), Inclusive));
+        assert_eq!(-Range::new(Raw(i64::MIN), Inclusive, Raw(0), Exclusive),
+                   Range::new(Raw(0), Exclusive, Max, Inclusive));
+    }
+
+    #[test]
+    fn neg_brute_force() {
+        check_brute_force(|a, _| -a, |x, _| Some(-x));
+    }
+
+    #[test]
+    fn not_brute_force() {
+        check_brute_force(|a, _| !a, |x, _| Some(!x));
+    }
+
+    #[test]
+    fn bitand_1() {
+        // x & 0xff
+        assert_eq!(Range::universe() & Range::new(Raw(0xff), Inclusive, Raw(0xff), Inclusive),
+                   Range::new(Raw(0), Inclusive, Raw(255), Inclusive));
+    }
+
+    #[test]
+    fn bitand_brute_force() {
+        check_brute_force_sound(|a, b| a & b, |x, y| Some(x & y));
+    }
+
+    #[test]
+    fn bitor_brute_force() {
+        check_brute_force_sound(|a, b| a | b, |x, y| Some(x | y));
+    }
+
+    #[test]
+    fn bitxor_brute_force() {
+        check_brute_force_sound(|a, b| a ^ b, |x, y| Some(x ^ y));
+    }
+
+    #[test]
+    fn shl_1() {
+        assert_eq!(Range::new(Raw(1), Inclusive, Raw(3), Inclusive)
+                   << Range::new(Raw(2), Inclusive, Raw(4), Inclusive),
+                   Range::new(Raw(4), Inclusive, Raw(48), Inclusive));
+        assert_eq!(Range::new(Raw(1), Inclusive, Raw(3), Inclusive)
+                   << Range::new(Raw(64), Inclusive, Max, Inclusive),
+                   Range::universe());
+    }
+
+    #[test]
+    fn shl_brute_force() {
+        check_brute_force_sound(|a, b| a << b, |x, y| if y < 0 { None } else { Some(x << y) });
+    }
+
+    #[test]
+    fn shr_brute_force() {
+        check_brute_force(|a, b| a >> b, |x, y| if y < 0 { None } else { Some(x >> y) });
     }
 }
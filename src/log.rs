@@ -0,0 +1,99 @@
+//! Optional tracing of the analysis.  Each entry is one line of JSON on
+//! stderr, so it never mixes with the report.  Nothing is logged unless a
+//! level is set.
+
+extern crate serde_json;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use self::serde_json::Value;
+
+/// How much to log.  Each level includes everything logged by the ones
+/// before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    /// Each function analyzed.
+    Info,
+    /// Each call summary computed.
+    Debug,
+    /// Every state visited, every join and widening, and every narrowing pass.
+    Trace,
+}
+
+impl Level {
+    /// The level named `name`, as written in its log entries.
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "off" => Some(Level::Off),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
+
+static LEVEL: AtomicUsize = AtomicUsize::new(Level::Off as usize);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as usize, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level != Level::Off && level as usize <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Writes an entry for `event`.  Use `log!`, which doesn't build `fields`
+/// unless `level` is enabled.
+pub fn write(level: Level, event: &str, fields: Value) {
+    eprintln!("{}", entry(level, event, fields));
+}
+
+fn entry(level: Level, event: &str, fields: Value) -> String {
+    let mut entry = match fields {
+        Value::Object(fields) => fields,
+        _ => panic!("log fields must be an object"),
+    };
+    entry.insert("level".to_string(), Value::from(format!("{:?}", level).to_lowercase()));
+    entry.insert("event".to_string(), Value::from(event));
+    Value::Object(entry).to_string()
+}
+
+/// Logs `event` at `level` with some `"key": value` fields, where each value
+/// is anything `json!` accepts.
+macro_rules! log {
+    ($level:ident, $event:expr $(, $key:tt : $value:expr)* $(,)*) => {
+        if ::log::enabled(::log::Level::$level) {
+            ::log::write(::log::Level::$level, $event, json!({ $($key: $value),* }));
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_1() {
+        assert_eq!(entry(Level::Trace, "visit", json!({"location": "a", "iteration": 3})),
+                   r#"{"event":"visit","iteration":3,"level":"trace","location":"a"}"#);
+    }
+
+    #[test]
+    fn levels() {
+        assert!(Level::Off < Level::Info);
+        assert!(Level::Info < Level::Debug);
+        assert!(Level::Debug < Level::Trace);
+        // Nothing is logged by default
+        assert!(!enabled(Level::Info));
+    }
+
+    #[test]
+    fn from_name() {
+        assert_eq!(Level::from_name("off"), Some(Level::Off));
+        assert_eq!(Level::from_name("debug"), Some(Level::Debug));
+        assert_eq!(Level::from_name("trace"), Some(Level::Trace));
+        assert_eq!(Level::from_name("Trace"), None);
+        assert_eq!(Level::from_name("verbose"), None);
+    }
+}
@@ -1,3 +1,6 @@
+#[macro_use]
+mod log;
+
 mod bounded_value;
 
 mod numerical_value;
@@ -11,14 +14,46 @@ use parse::*;
 
 #[macro_use]
 extern crate serde_derive;
+#[macro_use]
 extern crate serde_json;
 
 use std::io;
 use std::fs;
 
-fn parse_args() -> io::Result<(String, String)> {
-    let args: Vec<String> = ::std::env::args().skip(1).collect();
-    if args.len() == 0 {
+struct Args {
+    file_in: String,
+    file_out: String,
+    /// Whether to output the variables at every node.
+    invariants: bool,
+    /// How much of the analysis to log to stderr.
+    log_level: log::Level,
+}
+
+fn parse_args() -> io::Result<Args> {
+    let mut invariants = false;
+    let mut log_level = log::Level::Off;
+    let mut args = Vec::new();
+    let mut iter = ::std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--invariants" => invariants = true,
+            "--log-level" => {
+                let name = iter.next().unwrap_or_default();
+                log_level = log::Level::from_name(&name).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput,
+                                   format!("Unknown log level '{}'.  Should be one of off, info, debug or trace",
+                                           name))
+                })?;
+            },
+            // Kept from before there were levels
+            "--trace" => log_level = log::Level::Trace,
+            _ if arg.starts_with("--") =>
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          format!("Unknown option {}", arg))),
+            _ => args.push(arg),
+        }
+    }
+    if args.is_empty() {
         Err(io::Error::new(io::ErrorKind::InvalidInput, "No file_in argument"))
     } else if args.len() == 1 {
         Err(io::Error::new(io::ErrorKind::InvalidInput, "No file_out argument"))
@@ -26,7 +61,7 @@ fn parse_args() -> io::Result<(String, String)> {
         let mut iter = args.into_iter();
         let file_in = iter.next().unwrap();
         let file_out = iter.next().unwrap();
-        Ok((file_in, file_out))
+        Ok(Args { file_in, file_out, invariants, log_level })
     } else {
         Err(io::Error::new(io::ErrorKind::InvalidInput,
                            "Too many arguments.  Should be just file_in and file_out"))
@@ -34,12 +69,13 @@ fn parse_args() -> io::Result<(String, String)> {
 }
 
 fn main_() -> io::Result<()> {
-    let (file_in, file_out) = parse_args()?;
-    let graph = parse(&file_in)?;
-    let diagnostics = analyze(&graph);
-    let diagnostics: String = serde_json::to_string_pretty(&diagnostics)?;
-    println!("\n{}", diagnostics);
-    fs::write(&file_out, &diagnostics)?;
+    let Args { file_in, file_out, invariants, log_level } = parse_args()?;
+    log::set_level(log_level);
+    let program = parse(&file_in)?;
+    let report = analyze(&program, invariants);
+    let report: String = serde_json::to_string_pretty(&report)?;
+    println!("{}", report);
+    fs::write(&file_out, &report)?;
     Ok(())
 }
 
@@ -2,6 +2,8 @@ use std::collections::BTreeSet;
 use std::cmp::Ordering;
 use std::fmt;
 use std::clone::Clone;
+extern crate serde;
+use self::serde::{Serialize, Serializer};
 
 #[derive(PartialEq, Eq, Clone)]
 pub struct NumericalValue<T> {
@@ -131,37 +133,34 @@ impl<T> NumericalValue<T> where T: Ord, T: Clone {
                 }
             }
         }
-        match working_other {
-            Some(r) => {
-                let w = other.next();
-                match w {
-                    Some(w) => {
-                        // [ ]
-                        //   [ ]
-                        if r.max.value == w.min.value &&
-                            (r.max.inclusivity == Inclusivity::Inclusive ||
-                             w.min.inclusivity == Inclusivity::Inclusive) {
-                            new_ranges.insert(Range { min: r.min.clone(), max: w.max.clone() });
-                        }
-                        // [ ]
-                        //  [ ]
-                        else if r.min <= w.min && r.max >= w.min {
-                            if r.max > w.max {
-                                new_ranges.insert(Range { min: r.min.clone(), max: r.max.clone() });
-                            } else {
-                                new_ranges.insert(Range { min: r.min.clone(), max: w.max.clone() });
-                            }
+        if let Some(r) = working_other {
+            let w = other.next();
+            match w {
+                Some(w) => {
+                    // [ ]
+                    //   [ ]
+                    if r.max.value == w.min.value &&
+                        (r.max.inclusivity == Inclusivity::Inclusive ||
+                         w.min.inclusivity == Inclusivity::Inclusive) {
+                        new_ranges.insert(Range { min: r.min.clone(), max: w.max.clone() });
+                    }
+                    // [ ]
+                    //  [ ]
+                    else if r.min <= w.min && r.max >= w.min {
+                        if r.max > w.max {
+                            new_ranges.insert(Range { min: r.min.clone(), max: r.max.clone() });
                         } else {
-                            new_ranges.insert(r.clone());
-                            new_ranges.insert(w.clone());
+                            new_ranges.insert(Range { min: r.min.clone(), max: w.max.clone() });
                         }
-                    },
-                    None => {
+                    } else {
                         new_ranges.insert(r.clone());
+                        new_ranges.insert(w.clone());
                     }
+                },
+                None => {
+                    new_ranges.insert(r.clone());
                 }
-            },
-            None => {},
+            }
         }
         for w in other {
             new_ranges.insert(w.clone());
@@ -169,6 +168,7 @@ impl<T> NumericalValue<T> where T: Ord, T: Clone {
         NumericalValue { ranges: new_ranges }
     }
 
+    #[allow(dead_code)]
     pub fn union_value(&self, min_v: T, min_i: Inclusivity, max_v: T, max_i: Inclusivity) -> Self {
         self.union(&NumericalValue::new_value(min_v, min_i, max_v, max_i))
     }
@@ -196,52 +196,50 @@ impl<'a, T: 'a> NumericalValue<T> where T: Ord + Clone {
                 working_other = other.next();
             }
             loop {
-                match working_other.take() {
-                    Some(w) => {
-                        // [  ]]]
-                        //  [ ]]]
-                        if r.min <= w.min && r.max >= w.min {
-                            if r.max > w.max {
-                                // [   ]
-                                //  [ ]
-                                new_ranges.insert(w.clone());
-                                working_other = other.next();
-                                continue;
-                            } else {
-                                // [ ]
-                                //  [ ]
-                                new_ranges.insert(Range { min: w.min.clone(), max: r.max.clone() });
-                            }
-                        }
-                        //  [ ]]]
-                        // [  ]]]
-                        else if w.min <= r.min && w.max >= r.min {
-                            if w.max > r.max {
-                                //  [ ]
-                                // [   ]
-                                new_ranges.insert(r.clone());
-                                working_other = Some(w);
-                            } else {
-                                //  [ ]
-                                // [ ]
-                                new_ranges.insert(Range { min: r.min.clone(), max: w.max.clone() });
-                                working_other = other.next();
-                                continue;
-                            }
-                        }
-                        // [ ]
-                        //     [ ]
-                        else if r.max < w.min {
+                if let Some(w) = working_other.take() {
+                    // [  ]]]
+                    //  [ ]]]
+                    if r.min <= w.min && r.max >= w.min {
+                        if r.max > w.max {
+                            // [   ]
+                            //  [ ]
+                            new_ranges.insert(w.clone());
+                            working_other = other.next();
+                            continue;
+                        } else {
+                            // [ ]
+                            //  [ ]
+                            new_ranges.insert(Range { min: w.min.clone(), max: r.max.clone() });
                             working_other = Some(w);
                         }
-                        //     [ ]
-                        // [ ]
-                        else {
+                    }
+                    //  [ ]]]
+                    // [  ]]]
+                    else if w.min <= r.min && w.max >= r.min {
+                        if w.max > r.max {
+                            //  [ ]
+                            // [   ]
+                            new_ranges.insert(r.clone());
+                            working_other = Some(w);
+                        } else {
+                            //  [ ]
+                            // [ ]
+                            new_ranges.insert(Range { min: r.min.clone(), max: w.max.clone() });
                             working_other = other.next();
                             continue;
                         }
-                    },
-                    None => {},
+                    }
+                    // [ ]
+                    //     [ ]
+                    else if r.max < w.min {
+                        working_other = Some(w);
+                    }
+                    //     [ ]
+                    // [ ]
+                    else {
+                        working_other = other.next();
+                        continue;
+                    }
                 }
                 break;
             }
@@ -275,6 +273,7 @@ impl<T> Range<T> where T: MinMax + Clone {
                    T::max_value(), Inclusivity::Inclusive)
     }
 
+    #[allow(dead_code)]
     pub fn inverse(&self) -> (Range<T>, Range<T>) {
         (self.before(), self.after())
     }
@@ -305,13 +304,13 @@ pub trait MinMax {
 }
 
 impl MinMax for i32 {
-    fn min_value() -> Self { i32::min_value() }
-    fn max_value() -> Self { i32::max_value() }
+    fn min_value() -> Self { i32::MIN }
+    fn max_value() -> Self { i32::MAX }
 }
 
 impl MinMax for i64 {
-    fn min_value() -> Self { i64::min_value() }
-    fn max_value() -> Self { i64::max_value() }
+    fn min_value() -> Self { i64::MIN }
+    fn max_value() -> Self { i64::MAX }
 }
 
 impl<T> NumericalValue<T> where T: MinMax, T: Ord, T: Clone {
@@ -346,6 +345,42 @@ impl<T> NumericalValue<T> where T: MinMax, T: Ord, T: Clone {
         NumericalValue::new_value(T::min_value(), Inclusivity::Inclusive,
                                   T::max_value(), Inclusivity::Inclusive)
     }
+
+    /// Joins `self` (the previous value) with `other` into a single range,
+    /// jumping every bound that moved to the next value in `thresholds`, or
+    /// to infinity if there is none.  The gaps are filled in since ranges
+    /// between them could otherwise grow one value at a time.  Bounds can
+    /// only move a finite number of times, so repeatedly widening always
+    /// reaches a fixpoint.
+    pub fn widen(&self, other: &Self, thresholds: &BTreeSet<T>) -> Self {
+        let (old, new) = match (self.range(), other.range()) {
+            (Some(old), Some(new)) => (old, new),
+            _ => return self.union(other),
+        };
+        let mut min = old.min.clone();
+        if new.min < old.min {
+            let value = thresholds.range(..=new.min.value).next_back()
+                .cloned().unwrap_or_else(T::min_value);
+            min = MinPair { value, inclusivity: Inclusivity::Inclusive };
+        }
+        let mut max = old.max.clone();
+        if new.max > old.max {
+            let value = thresholds.range(new.max.value..).next()
+                .cloned().unwrap_or_else(T::max_value);
+            max = MaxPair { value, inclusivity: Inclusivity::Inclusive };
+        }
+        NumericalValue::from(Range { min, max })
+    }
+
+    /// Refines `self` (the widened value) with `other`, a value recomputed
+    /// from `self`.  Widening may have overshot any bound, not just the ones
+    /// it sent to infinity.
+    pub fn narrow(&self, other: &Self) -> Self {
+        match other.range() {
+            Some(new) => self.intersect_range(&new),
+            None => self.clone(),
+        }
+    }
 }
 
 impl Inclusivity {
@@ -358,6 +393,13 @@ impl Inclusivity {
     }
 }
 
+/// Serialized the same way it is printed, ie `[0, 3) U (5, inf]`.
+impl<T: fmt::Debug> Serialize for NumericalValue<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{:?}", self))
+    }
+}
+
 impl<T: fmt::Debug> fmt::Debug for NumericalValue<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut first = true;
@@ -365,12 +407,12 @@ impl<T: fmt::Debug> fmt::Debug for NumericalValue<T> {
             if first {
                 first = false;
             } else {
-                try!(write!(f, " U "));
+                write!(f, " U ")?;
             }
-            try!(write!(f, "{:?}", r));
+            write!(f, "{:?}", r)?;
         }
         if first {
-            try!(write!(f, "(0, 0)"));
+            write!(f, "(0, 0)")?;
         }
         Ok(())
     }
@@ -378,18 +420,16 @@ impl<T: fmt::Debug> fmt::Debug for NumericalValue<T> {
 
 impl<T: fmt::Debug> fmt::Debug for Range<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let seperator_min;
-        if self.min.inclusivity == Inclusivity::Inclusive {
-            seperator_min = "[";
+        let seperator_min = if self.min.inclusivity == Inclusivity::Inclusive {
+            "["
         } else {
-            seperator_min = "(";
-        }
-        let seperator_max;
-        if self.max.inclusivity == Inclusivity::Inclusive {
-            seperator_max = "]";
+            "("
+        };
+        let seperator_max = if self.max.inclusivity == Inclusivity::Inclusive {
+            "]"
         } else {
-            seperator_max = ")";
-        }
+            ")"
+        };
         write!(f, "{}{:?}, {:?}{}", seperator_min, self.min.value, self.max.value, seperator_max)
     }
 }
@@ -427,11 +467,11 @@ impl<T: PartialOrd> PartialOrd for MaxPair<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         self.value.partial_cmp(&other.value).map(|ordering| {
             if ordering == Ordering::Equal {
-                if (self.inclusivity == Inclusivity::Inclusive &&
-                    other.inclusivity == Inclusivity::Exclusive) {
+                if self.inclusivity == Inclusivity::Inclusive &&
+                    other.inclusivity == Inclusivity::Exclusive {
                     Ordering::Greater
-                } else if (self.inclusivity == Inclusivity::Exclusive &&
-                           other.inclusivity == Inclusivity::Inclusive) {
+                } else if self.inclusivity == Inclusivity::Exclusive &&
+                           other.inclusivity == Inclusivity::Inclusive {
                     Ordering::Less
                 } else {
                     Ordering::Equal
@@ -447,11 +487,11 @@ impl<T: PartialOrd> PartialOrd for MinPair<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         self.value.partial_cmp(&other.value).map(|ordering| {
             if ordering == Ordering::Equal {
-                if (self.inclusivity == Inclusivity::Inclusive &&
-                    other.inclusivity == Inclusivity::Exclusive) {
+                if self.inclusivity == Inclusivity::Inclusive &&
+                    other.inclusivity == Inclusivity::Exclusive {
                     Ordering::Less
-                } else if (self.inclusivity == Inclusivity::Exclusive &&
-                           other.inclusivity == Inclusivity::Inclusive) {
+                } else if self.inclusivity == Inclusivity::Exclusive &&
+                           other.inclusivity == Inclusivity::Inclusive {
                     Ordering::Greater
                 } else {
                     Ordering::Equal
@@ -467,8 +507,8 @@ impl<T: PartialOrd> PartialOrd<MaxPair<T>> for MinPair<T> {
     fn partial_cmp(&self, other: &MaxPair<T>) -> Option<Ordering> {
         self.value.partial_cmp(&other.value).map(|ordering| {
             if ordering == Ordering::Equal {
-                if (self.inclusivity == Inclusivity::Inclusive &&
-                    other.inclusivity == Inclusivity::Inclusive) {
+                if self.inclusivity == Inclusivity::Inclusive &&
+                    other.inclusivity == Inclusivity::Inclusive {
                     Ordering::Equal
                 } else {
                     Ordering::Greater
@@ -484,8 +524,8 @@ impl<T: PartialOrd> PartialOrd<MinPair<T>> for MaxPair<T> {
     fn partial_cmp(&self, other: &MinPair<T>) -> Option<Ordering> {
         self.value.partial_cmp(&other.value).map(|ordering| {
             if ordering == Ordering::Equal {
-                if (self.inclusivity == Inclusivity::Inclusive &&
-                    other.inclusivity == Inclusivity::Inclusive) {
+                if self.inclusivity == Inclusivity::Inclusive &&
+                    other.inclusivity == Inclusivity::Inclusive {
                     Ordering::Equal
                 } else {
                     Ordering::Less
@@ -596,6 +636,16 @@ mod tests {
         assert_eq!(format!("{:?}", n1.intersect(&n2)), "[2, 10)");
     }
 
+    #[test]
+    fn intersect_test_5() {
+        let value = NumericalValue::new_value(0, Inclusive, 0, Inclusive)
+            .union_value(2, Inclusive, 10, Inclusive);
+        assert_eq!(format!("{:?}", value.intersect_value(0, Inclusive, 20, Inclusive)),
+                   "[0, 0] U [2, 10]");
+        assert_eq!(format!("{:?}", value.intersect_value(-5, Inclusive, 5, Inclusive)),
+                   "[0, 0] U [2, 5]");
+    }
+
     #[test]
     fn inverse_test_1() {
         assert_eq!(format!("{:?}", NumericalValue::<i32>::new().inverse()), "[-2147483648, 2147483647]");
@@ -618,4 +668,53 @@ mod tests {
         assert_eq!(format!("{:?}", range.before()), "[-2147483648, -3]");
         assert_eq!(format!("{:?}", range.after()), "(4, 2147483647]");
     }
+
+    #[test]
+    fn widen_test_1() {
+        let thresholds = vec![10].into_iter().collect();
+        let value = NumericalValue::new_value(0, Inclusive, 1, Inclusive);
+        assert_eq!(format!("{:?}", value.widen(&NumericalValue::new_value(0, Inclusive, 2, Inclusive),
+                                               &thresholds)),
+                   "[0, 10]");
+        assert_eq!(format!("{:?}", value.widen(&NumericalValue::new_value(0, Inclusive, 11, Inclusive),
+                                               &thresholds)),
+                   "[0, 2147483647]");
+        assert_eq!(format!("{:?}", value.widen(&NumericalValue::new_value(-1, Inclusive, 1, Inclusive),
+                                               &thresholds)),
+                   "[-2147483648, 1]");
+        assert_eq!(format!("{:?}", value.widen(&NumericalValue::new_value(0, Inclusive, 1, Exclusive),
+                                               &thresholds)),
+                   "[0, 1]");
+    }
+
+    #[test]
+    fn widen_test_2() {
+        let thresholds = BTreeSet::new();
+        let value = NumericalValue::new_value(0, Inclusive, 1, Inclusive)
+            .union_value(5, Inclusive, 6, Inclusive);
+        assert_eq!(format!("{:?}", value.widen(&NumericalValue::new_value(3, Inclusive, 7, Inclusive),
+                                               &thresholds)),
+                   "[0, 2147483647]");
+        // Filling in the gap is enough to stop a range inside it from growing
+        let value = NumericalValue::new_value(0, Inclusive, 0, Inclusive)
+            .union_value(100, Inclusive, 100, Inclusive);
+        let widened = value.widen(&NumericalValue::new_value(1, Inclusive, 1, Inclusive), &thresholds);
+        assert_eq!(format!("{:?}", widened), "[0, 100]");
+        assert_eq!(widened.widen(&NumericalValue::new_value(1, Inclusive, 2, Inclusive), &thresholds),
+                   widened);
+    }
+
+    #[test]
+    fn narrow_test_1() {
+        let value = NumericalValue::new_value(0, Inclusive, i32::MAX, Inclusive);
+        assert_eq!(format!("{:?}", value.narrow(&NumericalValue::new_value(0, Inclusive, 10, Inclusive))),
+                   "[0, 10]");
+        assert_eq!(format!("{:?}", value.narrow(&NumericalValue::new_value(3, Inclusive, 10, Inclusive))),
+                   "[3, 10]");
+
+        // A bound widened to a threshold comes back down too
+        let value = NumericalValue::new_value(0, Inclusive, 20, Inclusive);
+        assert_eq!(format!("{:?}", value.narrow(&NumericalValue::new_value(0, Inclusive, 10, Inclusive))),
+                   "[0, 10]");
+    }
 }
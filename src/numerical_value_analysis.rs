@@ -1,20 +1,404 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::fmt;
+use std::ops::Index;
 use parse::*;
 use numerical_value::*;
 use bounded_value::*;
+use log;
 extern crate serde;
 extern crate serde_json;
 
 #[derive(Debug, Serialize, PartialEq, Eq)]
 pub struct Diagnostic {
+    /// The function `location` is in.
+    function: String,
     location: String,
-    always_true: bool,
+    kind: DiagnosticKind,
+    severity: Severity,
+    message: String,
+    /// The value of the left operand (or of the value being stored, or of
+    /// the index).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    left: Option<NumericalValue<BoundedValue<i64>>>,
+    /// The value of the right operand (or of what it is being stored in, or
+    /// of the length of the array being indexed).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    right: Option<NumericalValue<BoundedValue<i64>>>,
 }
 
-pub fn analyze(graph: &Graph) -> Vec<Diagnostic> {
+impl Diagnostic {
+    fn new(location: &str, kind: DiagnosticKind,
+           left: Option<NumericalValue<BoundedValue<i64>>>,
+           right: Option<NumericalValue<BoundedValue<i64>>>) -> Self {
+        Diagnostic {
+            function: String::new(),
+            location: location.to_string(),
+            kind,
+            severity: kind.severity(),
+            message: kind.to_string(),
+            left,
+            right,
+        }
+    }
+
+    /// Mentions the source of `node` in the message.
+    fn describe(&mut self, node: &NodeValue) {
+        self.message = format!("{}: `{}`", self.kind, node);
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The program is definitely wrong if it gets here.
+    Error,
+    /// The program might be wrong, or the code is suspicious.
+    Warning,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    /// A comparison that always passes.
+    AlwaysTrue,
+    /// A comparison that always fails.
+    AlwaysFalse,
+    /// The divisor of a `/` or `%` is always zero.
+    DivisionByZero,
+    /// The divisor of a `/` or `%` may be zero.
+    PossibleDivisionByZero,
+    /// The value stored in a variable never fits in its declared type.
+    Overflow,
+    /// The value stored in a variable may not fit in its declared type.
+    PossibleOverflow,
+    /// A node that can never run.
+    Unreachable,
+    /// A variable that is read before it is ever assigned.
+    Uninitialized,
+    /// A variable that is read before it is assigned on some path.
+    PossiblyUninitialized,
+    /// An index that is never inside its array.
+    OutOfBounds,
+    /// An index that may be outside its array.
+    PossibleOutOfBounds,
+    /// An assertion that never passes.
+    AssertionFailure,
+    /// An assertion that may not pass.
+    PossibleAssertionFailure,
+}
+
+impl DiagnosticKind {
+    fn severity(self) -> Severity {
+        use self::DiagnosticKind::*;
+        match self {
+            DivisionByZero | Overflow | Uninitialized | OutOfBounds | AssertionFailure =>
+                Severity::Error,
+            AlwaysTrue | AlwaysFalse | PossibleDivisionByZero | PossibleOverflow | Unreachable |
+            PossiblyUninitialized | PossibleOutOfBounds | PossibleAssertionFailure =>
+                Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::DiagnosticKind::*;
+        match self {
+            AlwaysTrue => write!(f, "comparison is always true"),
+            AlwaysFalse => write!(f, "comparison is always false"),
+            DivisionByZero => write!(f, "division by zero"),
+            PossibleDivisionByZero => write!(f, "possible division by zero"),
+            Overflow => write!(f, "value overflows its type"),
+            PossibleOverflow => write!(f, "value may overflow its type"),
+            Unreachable => write!(f, "unreachable code"),
+            Uninitialized => write!(f, "use of uninitialized variable"),
+            PossiblyUninitialized => write!(f, "use of possibly uninitialized variable"),
+            OutOfBounds => write!(f, "index is definitely out of bounds"),
+            PossibleOutOfBounds => write!(f, "index may be out of bounds"),
+            AssertionFailure => write!(f, "assertion always fails"),
+            PossibleAssertionFailure => write!(f, "assertion may fail"),
+        }
+    }
+}
+
+/// Everything the analysis found out about a program.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub diagnostics: Vec<Diagnostic>,
+    pub functions: Vec<FunctionReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FunctionReport {
+    pub name: String,
+    /// The join of every value the function can return, or `None` if it
+    /// never returns.
+    pub returns: Option<NumericalValue<BoundedValue<i64>>>,
+    /// What is known at each node that can run, if it was asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invariants: Option<BTreeMap<String, Invariant>>,
+}
+
+/// The values of the variables on entry to and exit from a node.
+#[derive(Debug, Serialize)]
+pub struct Invariant {
+    pub entry: BTreeMap<String, NumericalValue<BoundedValue<i64>>>,
+    /// `None` if execution never gets past the node.
+    pub exit: Option<BTreeMap<String, NumericalValue<BoundedValue<i64>>>>,
+}
+
+/// Analyzes every function in `program`, also computing their invariants if
+/// `invariants` is set.
+pub fn analyze(program: &Program, invariants: bool) -> Report {
     let mut diagnostics = Vec::new();
-    numerical_value_analysis(graph, graph.first(), &mut HashMap::new(), &mut HashMap::new(), &mut diagnostics);
-    diagnostics
+    let mut functions = Vec::new();
+    let mut summaries = Summaries::new(program);
+    for function in &program.functions {
+        log!(Info, "function", "function": function.name);
+        let first = diagnostics.len();
+        let states = numerical_value_analysis(&function.graph, &function.parameters, &[],
+                                              &mut summaries, &mut diagnostics);
+        for diagnostic in &mut diagnostics[first..] {
+            diagnostic.function = function.name.clone();
+        }
+        functions.push(FunctionReport {
+            name: function.name.clone(),
+            returns: return_value(&function.graph, &states, &mut summaries),
+            invariants: if invariants {
+                Some(function_invariants(function, &states, &mut summaries))
+            } else {
+                None
+            },
+        });
+    }
+    Report { diagnostics, functions }
+}
+
+/// The variables on entry to and exit from every node with a state in `states`.
+/// Arrays are left out, since what is stored for them isn't the value of any
+/// one variable.
+fn function_invariants(function: &Function, states: &HashMap<String, State>,
+                       summaries: &mut Summaries) -> BTreeMap<String, Invariant> {
+    let types = declared_types(&function.graph, &function.parameters);
+    let mut arrays = HashSet::new();
+    for (_, node) in function.graph.nodes() {
+        if let NodeValue::VariableDeclaration { declarations } = node {
+            for declaration in declarations.iter().filter(|declaration| declaration.length.is_some()) {
+                arrays.insert(declaration.identifier.clone());
+                arrays.insert(length_of(&declaration.identifier));
+            }
+        }
+    }
+    let reported = |variables: Variables| -> BTreeMap<_, _> {
+        variables.into_iter().filter(|(name, _)| !arrays.contains(name)).collect()
+    };
+    states.iter().map(|(location, state)| {
+        // The diagnostics were already reported by the analysis
+        let (exit, branches) = execute(location, function.graph.value_of(location).unwrap(),
+                                       state, &types, summaries, &mut Vec::new());
+        let exit = match branches {
+            Some((pass, fail)) => join_states(pass, fail),
+            None => Some(exit.variables),
+        };
+        (location.clone(), Invariant {
+            entry: reported(state.variables.clone()),
+            exit: exit.map(&reported),
+        })
+    }).collect()
+}
+
+/// The state on entry to a function, where each parameter holds whatever
+/// its declaration allows, narrowed to its argument if it was given one.
+fn entry_state(parameters: &[Parameter], arguments: &[NumericalValue<BoundedValue<i64>>]) -> State {
+    let variables: Variables = parameters.iter().enumerate().map(|(i, parameter)| {
+        let min = parameter.min.map_or(BoundedValue::Min, BoundedValue::Raw);
+        let max = parameter.max.map_or(BoundedValue::Max, BoundedValue::Raw);
+        let mut value = NumericalValue::new_value(min, Inclusivity::Inclusive,
+                                                  max, Inclusivity::Inclusive);
+        if let Some(integer_type) = parameter.integer_type {
+            value = value.intersect_range(&integer_type.range());
+        }
+        if let Some(argument) = arguments.get(i) {
+            // An argument the function doesn't allow is the caller's problem
+            let narrowed = value.intersect(argument);
+            if narrowed.range().is_some() {
+                value = narrowed;
+            }
+        }
+        (parameter.name.clone(), value)
+    }).collect();
+    State::from(variables)
+}
+
+type Arguments = Vec<NumericalValue<BoundedValue<i64>>>;
+/// The values a function returned for each set of arguments it was called with.
+type Summary = Vec<(Arguments, NumericalValue<BoundedValue<i64>>)>;
+
+/// The return values of functions, computed on demand for the values of
+/// their arguments at each call.
+#[derive(Default)]
+struct Summaries<'a> {
+    functions: HashMap<&'a str, &'a Function>,
+    returns: HashMap<String, Summary>,
+    /// The functions currently being analyzed, so recursion can be cut off.
+    active: HashSet<String>,
+}
+
+impl<'a> Summaries<'a> {
+    fn new(program: &'a Program) -> Self {
+        Summaries {
+            functions: program.functions.iter()
+                .map(|function| (function.name.as_str(), function))
+                .collect(),
+            ..Summaries::default()
+        }
+    }
+
+    /// The value returned by calling `name` with `arguments`.  Calls to
+    /// functions outside the program and recursive calls can return anything.
+    fn call(&mut self, name: &str, arguments: Arguments)
+            -> NumericalValue<BoundedValue<i64>> {
+        let function = match self.functions.get(name) {
+            Some(function) => *function,
+            None => return NumericalValue::universe(),
+        };
+        let returns = self.returns.entry(name.to_string()).or_default();
+        if let Some((_, value)) = returns.iter().find(|(seen, _)| *seen == arguments) {
+            return value.clone();
+        }
+        if !self.active.insert(name.to_string()) {
+            log!(Debug, "recursion", "function": name);
+            return NumericalValue::universe();
+        }
+        log!(Debug, "call", "function": name, "arguments": arguments);
+        // The callee reports its own diagnostics when it is analyzed by itself
+        let mut scratch = Vec::new();
+        let states = numerical_value_analysis(&function.graph, &function.parameters,
+                                              &arguments, self, &mut scratch);
+        // A function that never returns can't be called usefully either
+        let value = return_value(&function.graph, &states, self)
+            .unwrap_or_else(NumericalValue::universe);
+        self.active.remove(name);
+        log!(Debug, "summary", "function": name, "arguments": arguments, "returns": value);
+        self.returns.entry(name.to_string()).or_default().push((arguments, value.clone()));
+        value
+    }
+}
+
+/// The join of every value `graph` returns, or `None` if it never returns.
+fn return_value(graph: &Graph, states: &HashMap<String, State>, summaries: &mut Summaries)
+                -> Option<NumericalValue<BoundedValue<i64>>> {
+    let mut keys: Vec<&String> = states.keys().collect();
+    keys.sort();
+    let mut result: Option<NumericalValue<BoundedValue<i64>>> = None;
+    for key in keys {
+        if let Some(NodeValue::Return { value }) = graph.value_of(key) {
+            let mut variables = states[key].variables.clone();
+            let mut scratch = Vec::new();
+            let value = evaluate_calls(key, value, &mut variables, summaries, &mut scratch);
+            let value = parse_value_expression(&value, &variables);
+            result = Some(match result {
+                Some(result) => result.union(&value),
+                None => value,
+            });
+        }
+    }
+    result
+}
+
+/// Replaces every call in `node` with a temporary variable holding what it
+/// returns, so the rest of the analysis doesn't have to know about calls.
+fn evaluate_calls(location: &str, node: &Expression, variables: &mut Variables,
+                  summaries: &mut Summaries, diagnostics: &mut Vec<Diagnostic>) -> Expression {
+    use Expression::*;
+    match node {
+        Binary { left, op, right } => Binary {
+            left: Box::new(evaluate_calls(location, left, variables, summaries, diagnostics)),
+            op: op.clone(),
+            right: Box::new(evaluate_calls(location, right, variables, summaries, diagnostics)),
+        },
+        Unary { op, operand } => Unary {
+            op: op.clone(),
+            operand: Box::new(evaluate_calls(location, operand, variables, summaries, diagnostics)),
+        },
+        Call { function, arguments } => {
+            let values = arguments.iter().map(|argument| {
+                let argument = evaluate_calls(location, argument, variables, summaries, diagnostics);
+                check_division(location, &argument, variables, diagnostics);
+                check_indices(location, &argument, variables, diagnostics);
+                parse_value_expression(&argument, variables)
+            }).collect();
+            // Numbered rather than named after the call, which can print the
+            // same as a different one: id((1 + 2) * 3) and id(1 + (2 * 3))
+            let temporaries = variables.keys().filter(|name| name.starts_with(TEMPORARY_PREFIX)).count();
+            let name = format!("{}{}", TEMPORARY_PREFIX, temporaries);
+            variables.insert(name.clone(), summaries.call(function, values));
+            Identifier(name)
+        },
+        Index { array, index } => Index {
+            array: array.clone(),
+            index: Box::new(evaluate_calls(location, index, variables, summaries, diagnostics)),
+        },
+        Number(_) | Identifier(_) | Other => node.clone(),
+    }
+}
+
+/// Starts the names of the variables made by `evaluate_calls`, which can't
+/// clash with real identifiers.
+const TEMPORARY_PREFIX: &str = "@";
+
+type Variables = HashMap<String, NumericalValue<BoundedValue<i64>>>;
+type Types = HashMap<String, IntegerType>;
+
+/// What is known on entry to a node.  Variables that aren't in `variables`
+/// haven't been assigned on any path, or come from outside the function.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+struct State {
+    variables: Variables,
+    /// Variables declared in the function that haven't been assigned on
+    /// every path.
+    uninitialized: BTreeSet<String>,
+}
+
+impl State {
+    /// The state for the log, sorted so it reads the same every time.
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "variables": self.variables.iter().collect::<BTreeMap<_, _>>(),
+            "uninitialized": self.uninitialized,
+        })
+    }
+
+    /// Joins `other` into `self`, returning whether `self` grew.
+    fn join(&mut self, other: &State) -> bool {
+        self.merge(other, join)
+    }
+
+    /// Like `join`, but widens every variable that grew.
+    fn widen(&mut self, other: &State, thresholds: &BTreeSet<BoundedValue<i64>>) -> bool {
+        self.merge(other, |variables, other| widen(variables, other, thresholds))
+    }
+
+    fn merge<F>(&mut self, other: &State, merge: F) -> bool
+        where F: FnOnce(&mut Variables, &Variables) -> bool {
+        let grew = !other.uninitialized.is_subset(&self.uninitialized);
+        self.uninitialized.extend(other.uninitialized.iter().cloned());
+        merge(&mut self.variables, &other.variables) || grew
+    }
+}
+
+impl From<Variables> for State {
+    fn from(variables: Variables) -> Self {
+        State { variables, uninitialized: BTreeSet::new() }
+    }
+}
+
+impl Index<&str> for State {
+    type Output = NumericalValue<BoundedValue<i64>>;
+    fn index(&self, name: &str) -> &Self::Output {
+        &self.variables[name]
+    }
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -24,75 +408,713 @@ struct VariableValueSlice<T> {
     fail: Range<T>
 }
 
-fn numerical_value_analysis(graph: &Graph, location: &str,
-                            variables: &mut HashMap<String, NumericalValue<BoundedValue<i64>>>,
-                            history: &mut HashMap<String, HashMap<String, NumericalValue<BoundedValue<i64>>>>,
-                            diagnostics: &mut Vec<Diagnostic>) {
-    let node = graph.value_of(location).unwrap();
-    match history.get(location) {
-        Some(location_history) => {
-            let mut any_changed = false;
-            for (key, value) in location_history {
-                if variables.contains_key(key) {
-                    let (new_var, eq) = {
-                        let var = &variables[key];
-                        let new_var = var.union(value);
-                        let eq = *var != new_var;
-                        (new_var, eq)
+/// Number of descending iterations run after widening reaches a fixpoint.
+const NARROWING_PASSES: usize = 4;
+
+/// Computes the state on entry to every node reachable from `graph.first()`.
+///
+/// Nodes are processed from a worklist ordered by reverse postorder so that
+/// each node is visited after its predecessors (ignoring back edges).  The
+/// states flowing into a node are joined with `NumericalValue::union` and the
+/// node is only revisited when its entry state grows.  At loop heads the join
+/// is widened so that loops converge, then a few narrowing passes recover the
+/// precision lost by widening.
+fn numerical_value_analysis(graph: &Graph, parameters: &[Parameter],
+                            arguments: &[NumericalValue<BoundedValue<i64>>],
+                            summaries: &mut Summaries, diagnostics: &mut Vec<Diagnostic>)
+                            -> HashMap<String, State> {
+    let entry = entry_state(parameters, arguments);
+    let order = graph.reverse_postorder();
+    let loop_heads = graph.loop_heads();
+    let types = declared_types(graph, parameters);
+    let thresholds = thresholds(graph, &types);
+    let priority: HashMap<&str, usize> =
+        order.iter().enumerate().map(|(index, key)| (*key, index)).collect();
+    let mut states = HashMap::new();
+    states.insert(graph.first().clone(), entry.clone());
+    let mut worklist = BTreeSet::new();
+    worklist.insert(0);
+    // States before the fixpoint are under-approximations, so anything
+    // reported from them may not hold.
+    let mut scratch = Vec::new();
+
+    let mut iteration = 0;
+    while let Some(index) = worklist.pop_first() {
+        iteration += 1;
+        let location = order[index];
+        let successors = graph.successors_of(location).unwrap();
+        log!(Trace, "visit", "iteration": iteration, "location": location,
+             "state": states[location].to_json());
+        let outputs = transfer(location, graph.value_of(location).unwrap(),
+                               &states[location], successors, &types, summaries, &mut scratch);
+        for (succ, state) in successors.iter().zip(outputs) {
+            let state = match state {
+                Some(state) => state,
+                None => continue,
+            };
+            let changed = match states.entry(succ.key.clone()) {
+                Entry::Occupied(mut entry) => {
+                    let widened = loop_heads.contains(succ.key.as_str());
+                    let old = if log::enabled(log::Level::Trace) {
+                        Some(entry.get().clone())
+                    } else {
+                        None
                     };
-                    if eq {
-                        variables.insert(key.clone(), new_var);
-                        any_changed = true;
+                    let changed = if widened {
+                        entry.get_mut().widen(&state, &thresholds)
+                    } else {
+                        entry.get_mut().join(&state)
+                    };
+                    if let Some(old) = old {
+                        log!(Trace, if widened { "widen" } else { "join" },
+                             "iteration": iteration, "location": succ.key,
+                             "old": old.to_json(), "incoming": state.to_json(),
+                             "new": entry.get().to_json(), "changed": changed);
+                    }
+                    changed
+                },
+                Entry::Vacant(entry) => {
+                    entry.insert(state);
+                    true
+                },
+            };
+            if changed {
+                worklist.insert(priority[succ.key.as_str()]);
+            }
+        }
+    }
+
+    narrow(graph, &entry, &order, &loop_heads, &types, summaries, &mut states);
+
+    for location in &order {
+        if let Some(state) = states.get(*location) {
+            transfer(location, graph.value_of(location).unwrap(), state,
+                     graph.successors_of(location).unwrap(), &types, summaries, diagnostics);
+        }
+    }
+    report_unreachable(graph, &priority, &states, diagnostics);
+    states
+}
+
+/// Reports every node that no state reaches, in the order they would run.
+fn report_unreachable(graph: &Graph, priority: &HashMap<&str, usize>,
+                      states: &HashMap<String, State>, diagnostics: &mut Vec<Diagnostic>) {
+    let mut unreachable: Vec<_> = graph.nodes()
+        .filter(|(location, _)| !states.contains_key(*location))
+        .collect();
+    // Nodes the graph can't reach at all come last
+    unreachable.sort_by_key(|(location, _)| {
+        (priority.get(location.as_str()).cloned().unwrap_or(usize::MAX), location.as_str())
+    });
+    for (location, node) in unreachable {
+        let mut diagnostic = Diagnostic::new(location, DiagnosticKind::Unreachable, None, None);
+        diagnostic.describe(node);
+        diagnostics.push(diagnostic);
+    }
+}
+
+/// Runs descending iterations from the post-fixpoint `states`.  Each node's
+/// state is recomputed from its predecessors in reverse postorder, using the
+/// states already updated in this pass where possible.  At loop heads the new
+/// state is only used to refine bounds that widening sent to infinity.
+fn narrow(graph: &Graph, entry: &State, order: &[&str], loop_heads: &HashSet<&str>,
+          types: &Types, summaries: &mut Summaries, states: &mut HashMap<String, State>) {
+    let mut diagnostics = Vec::new();
+    let mut predecessors: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut outputs = HashMap::new();
+    for location in order {
+        let successors = graph.successors_of(location).unwrap();
+        for succ in successors {
+            let preds = predecessors.entry(succ.key.as_str()).or_default();
+            if !preds.contains(location) {
+                preds.push(location);
+            }
+        }
+        outputs.insert(*location, match states.get(*location) {
+            Some(state) => transfer(location, graph.value_of(location).unwrap(),
+                                    state, successors, types, summaries, &mut diagnostics),
+            None => vec![None; successors.len()],
+        });
+    }
+
+    for pass in 0..NARROWING_PASSES {
+        let mut any_changed = false;
+        for location in order {
+            let mut incoming = if *location == graph.first() {
+                Some(entry.clone())
+            } else {
+                None
+            };
+            for pred in predecessors.get(location).map_or(&[][..], |p| &p[..]) {
+                let successors = graph.successors_of(pred).unwrap();
+                for (succ, state) in successors.iter().zip(&outputs[pred]) {
+                    if let (true, Some(state)) = (succ.key == *location, state) {
+                        match &mut incoming {
+                            Some(incoming) => { incoming.join(state); },
+                            None => incoming = Some(state.clone()),
+                        }
                     }
-                } else {
-                    variables.insert(key.clone(), value.clone());
-                    any_changed = true;
                 }
             }
-            if history[location].is_empty() && !variables.is_empty() {
+            let new_state = match (incoming, states.get(*location)) {
+                (Some(incoming), Some(old_state)) if loop_heads.contains(location) => {
+                    let variables = old_state.variables.iter().map(|(key, value)| {
+                        (key.clone(), match incoming.variables.get(key) {
+                            Some(new_value) => value.narrow(new_value),
+                            None => value.clone(),
+                        })
+                    }).collect();
+                    Some(State { variables, uninitialized: old_state.uninitialized.clone() })
+                },
+                (incoming, _) => incoming,
+            };
+            if states.get(*location) != new_state.as_ref() {
                 any_changed = true;
+                log!(Trace, "narrow", "pass": pass, "location": location,
+                     "state": new_state.as_ref().map(State::to_json));
+                let successors = graph.successors_of(location).unwrap();
+                match new_state {
+                    Some(new_state) => {
+                        outputs.insert(*location, transfer(location, graph.value_of(location).unwrap(),
+                                                           &new_state, successors, types,
+                                                           summaries, &mut diagnostics));
+                        states.insert(location.to_string(), new_state);
+                    },
+                    None => {
+                        outputs.insert(*location, vec![None; successors.len()]);
+                        states.remove(*location);
+                    },
+                }
             }
-            if !any_changed {
-                return;
-            }
-        },
-        None => {}
+        }
+        if !any_changed {
+            break;
+        }
     }
-    history.insert(location.to_string(), variables.clone());
+}
 
-    let mut slices = Vec::new();
+/// Applies `node` to the state on entry to it, returning the state flowing
+/// along each of `successors`, or `None` if that edge can never be taken.
+fn transfer(location: &str, node: &NodeValue, state: &State,
+            successors: &[Successor], types: &Types, summaries: &mut Summaries,
+            diagnostics: &mut Vec<Diagnostic>) -> Vec<Option<State>> {
+    let (state, branches) = execute(location, node, state, types, summaries, diagnostics);
+    successors.iter().map(|succ| {
+        let variables = match &branches {
+            // Assertions and assumptions only continue if they pass
+            Some((pass, fail)) =>
+                if succ.value == 0 { fail.clone() }
+                else { pass.clone() },
+            None => Some(state.variables.clone()),
+        };
+        variables.map(|variables| State { variables, uninitialized: state.uninitialized.clone() })
+    }).collect()
+}
+
+/// The variables along the pass and fail edges of a node, or `None` if that
+/// edge can never be taken.
+type Branches = (Option<Variables>, Option<Variables>);
+
+/// Applies `node` to the state on entry to it, returning the state after it
+/// and the branches it splits that state into, if it does.
+fn execute(location: &str, node: &NodeValue, state: &State, types: &Types,
+           summaries: &mut Summaries, diagnostics: &mut Vec<Diagnostic>)
+           -> (State, Option<Branches>) {
+    let mut variables = state.variables.clone();
+    let mut uninitialized = state.uninitialized.clone();
+    let mut branches = None;
+    let first_diagnostic = diagnostics.len();
     match node {
         NodeValue::VariableDeclaration { declarations } => {
             for declaration in declarations {
-                let parsed = parse_value_expression(&declaration.initializer, &variables);
-                variables.insert(declaration.identifier.clone(), parsed);
+                let name = &declaration.identifier;
+                uninitialized.remove(name);
+                match (&declaration.length, &declaration.initializer) {
+                    (Some(length), _) => {
+                        check_initialized(location, length, &mut variables,
+                                          &mut uninitialized, diagnostics);
+                        let length = evaluate_calls(location, length, &mut variables,
+                                                    summaries, diagnostics);
+                        assign(location, &length_of(name), &length, &mut variables, types,
+                               diagnostics);
+                        uninitialized.remove(&length_of(name));
+                        // Nothing is known about what the elements start out as
+                        variables.insert(name.clone(), types.get(name).map_or_else(
+                            NumericalValue::universe,
+                            |integer_type| NumericalValue::from(integer_type.range())));
+                    },
+                    (None, Some(initializer)) => {
+                        check_initialized(location, initializer, &mut variables,
+                                          &mut uninitialized, diagnostics);
+                        let initializer = evaluate_calls(location, initializer, &mut variables,
+                                                         summaries, diagnostics);
+                        assign(location, name, &initializer, &mut variables, types, diagnostics);
+                    },
+                    (None, None) => {
+                        variables.remove(name);
+                        uninitialized.insert(name.clone());
+                    },
+                }
+            }
+        },
+        NodeValue::VariableAssignment { left, op, right } => {
+            let value = if op == "=" {
+                right.clone()
+            } else {
+                // a += b is a = a + b
+                Expression::Binary {
+                    left: Box::new(left.clone()),
+                    op: op.trim_end_matches('=').to_string(),
+                    right: Box::new(right.clone()),
+                }
+            };
+            check_initialized(location, &value, &mut variables, &mut uninitialized, diagnostics);
+            match left {
+                Expression::Identifier(name) => {
+                    let value = evaluate_calls(location, &value, &mut variables, summaries,
+                                               diagnostics);
+                    assign(location, name, &value, &mut variables, types, diagnostics);
+                    uninitialized.remove(name);
+                },
+                Expression::Index { .. } => {
+                    check_initialized(location, left, &mut variables, &mut uninitialized,
+                                      diagnostics);
+                    let left = evaluate_calls(location, left, &mut variables, summaries,
+                                              diagnostics);
+                    let value = evaluate_calls(location, &value, &mut variables, summaries,
+                                               diagnostics);
+                    check_indices(location, &left, &mut variables, diagnostics);
+                    if let Expression::Index { array, .. } = &left {
+                        assign_element(location, array, &value, &mut variables, types,
+                                       diagnostics);
+                    }
+                },
+                // Stores somewhere the analysis doesn't know about
+                _ => {
+                    let value = evaluate_calls(location, &value, &mut variables, summaries,
+                                               diagnostics);
+                    check_division(location, &value, &mut variables, diagnostics);
+                    check_indices(location, &value, &mut variables, diagnostics);
+                },
             }
         },
-        NodeValue::VariableAssignment { left, right } => {
-            let parsed = parse_value_expression(&right, &variables);
-            variables.insert(left.clone(), parsed);
+        NodeValue::Increment { identifier, op, .. } => {
+            let value = Expression::Binary {
+                left: Box::new(Expression::Identifier(identifier.clone())),
+                op: if op == "--" { "-" } else { "+" }.to_string(),
+                right: Box::new(Expression::Number(1)),
+            };
+            check_initialized(location, &value, &mut variables, &mut uninitialized, diagnostics);
+            assign(location, identifier, &value, &mut variables, types, diagnostics);
         },
         NodeValue::Comparison { left, op, right } => {
-            handle_comparison(location, left, op, right, variables, &mut slices, diagnostics);
+            check_initialized(location, left, &mut variables, &mut uninitialized, diagnostics);
+            check_initialized(location, right, &mut variables, &mut uninitialized, diagnostics);
+            let left = evaluate_calls(location, left, &mut variables, summaries, diagnostics);
+            let right = evaluate_calls(location, right, &mut variables, summaries, diagnostics);
+            branches = Some(refine_condition(location, &left, op, &right, &variables, diagnostics));
+        },
+        NodeValue::Call { function, arguments } => {
+            let call = Expression::Call { function: function.clone(), arguments: arguments.clone() };
+            check_initialized(location, &call, &mut variables, &mut uninitialized, diagnostics);
+            evaluate_calls(location, &call, &mut variables, summaries, diagnostics);
+        },
+        NodeValue::Return { value } => {
+            check_initialized(location, value, &mut variables, &mut uninitialized, diagnostics);
+            let value = evaluate_calls(location, value, &mut variables, summaries, diagnostics);
+            check_division(location, &value, &mut variables, diagnostics);
+            check_indices(location, &value, &mut variables, diagnostics);
+        },
+        NodeValue::Assert { condition } | NodeValue::Assume { condition } => {
+            // An assumption is taken on trust, so nothing about it is reported
+            let mut ignored = Vec::new();
+            let reported = match node {
+                NodeValue::Assume { .. } => &mut ignored,
+                _ => &mut *diagnostics,
+            };
+            check_initialized(location, condition, &mut variables, &mut uninitialized, reported);
+            let condition = evaluate_calls(location, condition, &mut variables, summaries,
+                                           reported);
+            let mut parts = Vec::new();
+            let (pass, fail) = refine_operand(location, &condition, Some(&variables), &mut parts);
+            // Only whether the whole condition can fail matters
+            reported.extend(parts.into_iter().filter(|diagnostic| {
+                !matches!(diagnostic.kind, DiagnosticKind::AlwaysTrue | DiagnosticKind::AlwaysFalse)
+            }));
+            if let NodeValue::Assert { .. } = node {
+                let kind = match (&pass, &fail) {
+                    (_, None) => None,
+                    (None, Some(_)) => Some(DiagnosticKind::AssertionFailure),
+                    (Some(_), Some(_)) => Some(DiagnosticKind::PossibleAssertionFailure),
+                };
+                if let Some(kind) = kind {
+                    reported.push(Diagnostic::new(location, kind, None, None));
+                }
+            }
+            branches = Some((pass.clone(), pass));
         },
         NodeValue::Other => {},
     }
+    for diagnostic in &mut diagnostics[first_diagnostic..] {
+        diagnostic.describe(node);
+    }
+    // Nothing runs after a division that is always by zero
+    if diagnostics[first_diagnostic..].iter().any(|diagnostic| {
+        diagnostic.kind == DiagnosticKind::DivisionByZero
+    }) {
+        branches = Some((None, None));
+    }
 
-    println!("{} -> {:?} ({})", location, variables, node);
+    let is_real = |name: &String, _: &mut NumericalValue<BoundedValue<i64>>| {
+        !name.starts_with(TEMPORARY_PREFIX)
+    };
+    variables.retain(is_real);
+    let branches = branches.map(|(mut pass, mut fail): Branches| {
+        for variables in pass.iter_mut().chain(fail.iter_mut()) {
+            variables.retain(is_real);
+        }
+        (pass, fail)
+    });
+    (State { variables, uninitialized }, branches)
+}
 
-    for succ in graph.successors_of(location).unwrap() {
-        let mut vars = variables.clone();
-        for slice in slices.iter() {
-            let new_var = vars[&slice.name].intersect_range(
-                if succ.value == 1 { &slice.pass }
-                else if succ.value == 0 { &slice.fail }
-                else { unreachable!() });
-            vars.insert(slice.name.clone(), new_var);
+/// Reports every variable declared in the function and read by `node` that
+/// may not have been assigned.  Its value is garbage, so it is treated as
+/// unknown from then on.  Variables from outside the function are left
+/// alone, as they may have been assigned before it was called.
+fn check_initialized(location: &str, node: &Expression, variables: &mut Variables,
+                     uninitialized: &mut BTreeSet<String>, diagnostics: &mut Vec<Diagnostic>) {
+    match node {
+        Expression::Binary { left, right, .. } => {
+            check_initialized(location, left, variables, uninitialized, diagnostics);
+            check_initialized(location, right, variables, uninitialized, diagnostics);
+        },
+        Expression::Unary { operand, .. } =>
+            check_initialized(location, operand, variables, uninitialized, diagnostics),
+        Expression::Call { arguments, .. } => {
+            for argument in arguments {
+                check_initialized(location, argument, variables, uninitialized, diagnostics);
+            }
+        },
+        // Elements are assumed to be initialized, since which ones were isn't tracked
+        Expression::Index { index, .. } =>
+            check_initialized(location, index, variables, uninitialized, diagnostics),
+        Expression::Identifier(name) => {
+            if !uninitialized.contains(name) {
+                return;
+            }
+            let kind = if variables.contains_key(name) {
+                DiagnosticKind::PossiblyUninitialized
+            } else {
+                DiagnosticKind::Uninitialized
+            };
+            diagnostics.push(Diagnostic::new(location, kind, None, None));
+            variables.insert(name.clone(), NumericalValue::universe());
+            uninitialized.remove(name);
+        },
+        Expression::Number(_) | Expression::Other => {},
+    }
+}
+
+/// Stores the value of `node` in the variable `name`.
+fn assign(location: &str, name: &str, node: &Expression, variables: &mut Variables,
+          types: &Types, diagnostics: &mut Vec<Diagnostic>) {
+    check_division(location, node, variables, diagnostics);
+    check_indices(location, node, variables, diagnostics);
+    let value = match (node, types.get(name)) {
+        // An unknown value still has to be of the right type
+        (Expression::Other, Some(integer_type)) => NumericalValue::from(integer_type.range()),
+        _ => {
+            let value = parse_value_expression(node, variables);
+            check_overflow(location, name, value, types, diagnostics)
+        },
+    };
+    variables.insert(name.to_string(), value);
+}
+
+/// Stores the value of `node` in some element of `array`.  The elements
+/// share one value, which has to keep what the other elements hold.
+fn assign_element(location: &str, array: &str, node: &Expression, variables: &mut Variables,
+                  types: &Types, diagnostics: &mut Vec<Diagnostic>) {
+    let old = variables.get(array).cloned();
+    assign(location, array, node, variables, types, diagnostics);
+    match old {
+        Some(old) => {
+            let value = variables[array].union(&old);
+            variables.insert(array.to_string(), value);
+        },
+        // The other elements of an array from outside the function could be anything
+        None => {
+            variables.remove(array);
+        },
+    }
+}
+
+/// The variable holding the length of `array`.  The `.` stops it from
+/// clashing with real identifiers.
+fn length_of(array: &str) -> String {
+    format!("{}.length", array)
+}
+
+/// Reports every index in `node` that may be outside its array.  Execution
+/// only continues past the node if it wasn't, so indices that are plain
+/// variables are refined to fit.  Arrays from outside the function have an
+/// unknown length and are never reported.
+fn check_indices(location: &str, node: &Expression, variables: &mut Variables,
+                 diagnostics: &mut Vec<Diagnostic>) {
+    match node {
+        Expression::Binary { left, right, .. } => {
+            check_indices(location, left, variables, diagnostics);
+            check_indices(location, right, variables, diagnostics);
+        },
+        Expression::Unary { operand, .. } => check_indices(location, operand, variables, diagnostics),
+        Expression::Index { array, index } => {
+            check_indices(location, index, variables, diagnostics);
+            let length = match variables.get(&length_of(array)) {
+                Some(length) => length.clone(),
+                None => return,
+            };
+            let value = parse_value_expression(index, variables);
+            let (i, n) = match (value.range(), length.range()) {
+                (Some(i), Some(n)) => (i, n),
+                _ => return,
+            };
+            let zero = Range::from(BoundedValue::Raw(0));
+            if i.min >= zero.max && i.max < n.min {
+                return;
+            }
+            // The indices that fit some possible length.  With a gap, eg
+            // [-5, -5] U [20, 20], there can be none even though the index
+            // spans the array.
+            let fits = if n.max.value > BoundedValue::Raw(0) {
+                value.intersect_value(BoundedValue::Raw(0), Inclusivity::Inclusive,
+                                      n.max.value, Inclusivity::Exclusive)
+            } else {
+                NumericalValue::new()
+            };
+            let kind = if fits.range().is_none() {
+                DiagnosticKind::OutOfBounds
+            } else {
+                DiagnosticKind::PossibleOutOfBounds
+            };
+            diagnostics.push(Diagnostic::new(location, kind, Some(value.clone()), Some(length)));
+            if let (DiagnosticKind::PossibleOutOfBounds, Expression::Identifier(name)) = (kind, &**index) {
+                variables.insert(name.clone(), fits);
+            }
+        },
+        Expression::Call { .. } | Expression::Number(_) | Expression::Identifier(_) |
+        Expression::Other => {},
+    }
+}
+
+/// Reports `value` not fitting in the declared type of the variable `name`
+/// it is being stored in.  Integers wrap around when they overflow, so the
+/// variable could end up holding anything its type allows.
+fn check_overflow(location: &str, name: &str, value: NumericalValue<BoundedValue<i64>>,
+                  types: &Types, diagnostics: &mut Vec<Diagnostic>)
+                  -> NumericalValue<BoundedValue<i64>> {
+    let range = match types.get(name) {
+        Some(integer_type) => integer_type.range(),
+        None => return value,
+    };
+    let fits = value.intersect_range(&range);
+    if fits == value {
+        return value;
+    }
+    let kind = if fits.range().is_none() {
+        DiagnosticKind::Overflow
+    } else {
+        DiagnosticKind::PossibleOverflow
+    };
+    let range = NumericalValue::from(range);
+    diagnostics.push(Diagnostic::new(location, kind, Some(value), Some(range.clone())));
+    range
+}
+
+impl IntegerType {
+    /// The values a variable of this type can hold.
+    fn range(self) -> Range<BoundedValue<i64>> {
+        let (min, max) = self.bounds();
+        let max = max.map_or(BoundedValue::Max, BoundedValue::Raw);
+        Range::new(BoundedValue::Raw(min), Inclusivity::Inclusive, max, Inclusivity::Inclusive)
+    }
+}
+
+/// The declared type of every variable that has one.
+fn declared_types(graph: &Graph, parameters: &[Parameter]) -> Types {
+    let mut types = Types::new();
+    for parameter in parameters {
+        if let Some(integer_type) = parameter.integer_type {
+            types.insert(parameter.name.clone(), integer_type);
+        }
+    }
+    for (_, node) in graph.nodes() {
+        if let NodeValue::VariableDeclaration { declarations } = node {
+            for declaration in declarations {
+                if let Some(integer_type) = declaration.integer_type {
+                    types.insert(declaration.identifier.clone(), integer_type);
+                }
+            }
+        }
+    }
+    types
+}
+
+/// Reports every `/` and `%` in `node` whose divisor may be zero.  Execution
+/// only continues past the node if the divisor wasn't zero, so divisors that
+/// are plain variables are refined to exclude zero.  Any other divisor, such
+/// as `a + 1`, is left as it is.
+fn check_division(location: &str, node: &Expression, variables: &mut Variables,
+                  diagnostics: &mut Vec<Diagnostic>) {
+    if let Expression::Unary { operand, .. } = node {
+        check_division(location, operand, variables, diagnostics);
+    }
+    if let Expression::Index { index, .. } = node {
+        check_division(location, index, variables, diagnostics);
+    }
+    if let Expression::Binary { left, op, right } = node {
+        check_division(location, left, variables, diagnostics);
+        check_division(location, right, variables, diagnostics);
+        if op != "/" && op != "%" {
+            return;
+        }
+        let zero = NumericalValue::from(BoundedValue::Raw(0));
+        let divisor = parse_value_expression(right, variables);
+        if divisor == zero {
+            let dividend = parse_value_expression(left, variables);
+            diagnostics.push(Diagnostic::new(location, DiagnosticKind::DivisionByZero,
+                                             Some(dividend), Some(divisor)));
+        } else if divisor.intersect(&zero) == zero {
+            let dividend = parse_value_expression(left, variables);
+            diagnostics.push(Diagnostic::new(location, DiagnosticKind::PossibleDivisionByZero,
+                                             Some(dividend), Some(divisor.clone())));
+            if let Expression::Identifier(name) = &**right {
+                variables.insert(name.clone(), divisor.intersect(&zero.inverse()));
+            }
         }
-        numerical_value_analysis(graph, &succ.key, &mut vars, history, diagnostics);
     }
 }
 
+/// Joins two states that may not be reached.
+fn join_states(a: Option<Variables>, b: Option<Variables>) -> Option<Variables> {
+    match (a, b) {
+        (Some(mut a), Some(b)) => {
+            join(&mut a, &b);
+            Some(a)
+        },
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// Joins `other` into `variables`, returning whether `variables` grew.
+fn join(variables: &mut Variables, other: &Variables) -> bool {
+    let mut changed = false;
+    for (key, value) in other {
+        match variables.entry(key.clone()) {
+            Entry::Occupied(mut entry) => {
+                let new_value = entry.get().union(value);
+                if *entry.get() != new_value {
+                    entry.insert(new_value);
+                    changed = true;
+                }
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(value.clone());
+                changed = true;
+            },
+        }
+    }
+    changed
+}
+
+/// Like `join`, but widens every variable that grew.
+fn widen(variables: &mut Variables, other: &Variables,
+         thresholds: &BTreeSet<BoundedValue<i64>>) -> bool {
+    let mut changed = false;
+    for (key, value) in other {
+        match variables.entry(key.clone()) {
+            Entry::Occupied(mut entry) => {
+                let new_value = entry.get().widen(value, thresholds);
+                if *entry.get() != new_value {
+                    entry.insert(new_value);
+                    changed = true;
+                }
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(value.clone());
+                changed = true;
+            },
+        }
+    }
+    changed
+}
+
+/// Every constant in the graph and the limits of every declared type.
+/// Widening jumps to these before giving up and going to infinity, since loop
+/// bounds are usually one of them.
+fn thresholds(graph: &Graph, types: &Types) -> BTreeSet<BoundedValue<i64>> {
+    fn collect(node: &Expression, thresholds: &mut BTreeSet<BoundedValue<i64>>) {
+        match node {
+            Expression::Binary { left, right, .. } => {
+                collect(left, thresholds);
+                collect(right, thresholds);
+            },
+            Expression::Unary { operand, .. } => collect(operand, thresholds),
+            Expression::Call { arguments, .. } => {
+                for argument in arguments {
+                    collect(argument, thresholds);
+                }
+            },
+            Expression::Index { index, .. } => collect(index, thresholds),
+            Expression::Number(num) => {
+                thresholds.insert(BoundedValue::Raw(*num));
+            },
+            Expression::Identifier(_) | Expression::Other => {},
+        }
+    }
+
+    let mut thresholds = BTreeSet::new();
+    for integer_type in types.values() {
+        let range = integer_type.range();
+        thresholds.insert(range.min.value);
+        thresholds.insert(range.max.value);
+    }
+    for (_, node) in graph.nodes() {
+        match node {
+            NodeValue::VariableDeclaration { declarations } => {
+                for declaration in declarations {
+                    if let Some(initializer) = &declaration.initializer {
+                        collect(initializer, &mut thresholds);
+                    }
+                    if let Some(length) = &declaration.length {
+                        collect(length, &mut thresholds);
+                    }
+                }
+            },
+            NodeValue::VariableAssignment { left, right, .. } => {
+                collect(left, &mut thresholds);
+                collect(right, &mut thresholds);
+            },
+            NodeValue::Increment { .. } => {},
+            NodeValue::Comparison { left, right, .. } => {
+                collect(left, &mut thresholds);
+                collect(right, &mut thresholds);
+            },
+            NodeValue::Call { arguments, .. } => {
+                for argument in arguments {
+                    collect(argument, &mut thresholds);
+                }
+            },
+            NodeValue::Return { value } => collect(value, &mut thresholds),
+            NodeValue::Assert { condition } | NodeValue::Assume { condition } =>
+                collect(condition, &mut thresholds),
+            NodeValue::Other => {},
+        }
+    }
+    thresholds
+}
+
 fn parse_value_expression(node: &Expression, variables: &HashMap<String, NumericalValue<BoundedValue<i64>>>)
                           -> NumericalValue<BoundedValue<i64>> {
     use Expression::*;
@@ -106,16 +1128,54 @@ fn parse_value_expression(node: &Expression, variables: &HashMap<String, Numeric
                 "*" => l * r,
                 "/" => l / r,
                 "%" => l % r,
-                _ => unreachable!(),
+                "&" => l & r,
+                "|" => l | r,
+                "^" => l ^ r,
+                "<<" => l << r,
+                ">>" => l >> r,
+                op if is_condition(op) => return boolean(),
+                _ => Range::universe(),
             })
         },
+        Unary { op, operand } => match op.as_str() {
+            "-" => NumericalValue::from(-parse_value_expression(operand, variables).range().unwrap()),
+            "+" => parse_value_expression(operand, variables),
+            "~" => NumericalValue::from(!parse_value_expression(operand, variables).range().unwrap()),
+            "!" => {
+                let zero = NumericalValue::from(BoundedValue::Raw(0));
+                let value = parse_value_expression(operand, variables);
+                if value == zero {
+                    NumericalValue::from(BoundedValue::Raw(1))
+                } else if value.intersect(&zero) != zero {
+                    zero
+                } else {
+                    boolean()
+                }
+            },
+            _ => NumericalValue::universe(),
+        },
+        // Calls are replaced by `evaluate_calls` before they get here
+        Call { .. } => NumericalValue::universe(),
+        Index { array, .. } => variables.get(array).cloned().unwrap_or_else(NumericalValue::universe),
         Number(num) => NumericalValue::from(BoundedValue::Raw(*num)),
-        Identifier(var) => variables[var].clone(),
+        // Nothing is known about variables from outside the function
+        Identifier(var) => variables.get(var).cloned().unwrap_or_else(NumericalValue::universe),
         Other => NumericalValue::new_value(BoundedValue::Min, Inclusivity::Inclusive,
                                            BoundedValue::Max, Inclusivity::Inclusive),
     }
 }
 
+/// The value of a condition that could go either way.
+fn boolean() -> NumericalValue<BoundedValue<i64>> {
+    NumericalValue::new_value(BoundedValue::Raw(0), Inclusivity::Inclusive,
+                              BoundedValue::Raw(1), Inclusivity::Inclusive)
+}
+
+/// Whether `op` is a relational or logical operator, whose result is 0 or 1.
+fn is_condition(op: &str) -> bool {
+    matches!(op, "<" | "<=" | ">" | ">=" | "==" | "!=" | "&&" | "||")
+}
+
 #[derive(Clone, Copy, Debug)]
 enum ComparisonOperator {
     Less, LessEqual, Greater, GreaterEqual, Equals, NotEquals,
@@ -131,18 +1191,6 @@ impl ComparisonOperator {
             Equals | NotEquals => self,
         }
     }
-
-    fn not(self) -> Self {
-        use self::ComparisonOperator::*;
-        match self {
-            Less => GreaterEqual,
-            LessEqual => Greater,
-            Greater => LessEqual,
-            GreaterEqual => Less,
-            Equals => NotEquals,
-            NotEquals => Equals,
-        }
-    }
 }
 
 fn descend(node: &Expression, range: Range<BoundedValue<i64>>, cmp_op: ComparisonOperator,
@@ -162,7 +1210,7 @@ fn descend(node: &Expression, range: Range<BoundedValue<i64>>, cmp_op: Compariso
                         if range.max.inclusivity == Inclusive {
                             (range.max.value, Exclusive)
                         } else {
-                            (range.max.value + (-1).into(), Exclusive)
+                            (range.max.value.offset(-1), Exclusive)
                         };
                     pr = Range::new(BoundedValue::Min, Inclusive, max_v, max_i);
                     fr = Range::new(range.min.value, range.min.inclusivity,
@@ -173,7 +1221,7 @@ fn descend(node: &Expression, range: Range<BoundedValue<i64>>, cmp_op: Compariso
                         if range.min.inclusivity == Inclusive {
                             (range.min.value, Exclusive)
                         } else {
-                            (range.min.value + 1.into(), Exclusive)
+                            (range.min.value.offset(1), Exclusive)
                         };
                     pr = Range::new(BoundedValue::Min, Inclusive,
                                     range.max.value, range.max.inclusivity);
@@ -185,7 +1233,7 @@ fn descend(node: &Expression, range: Range<BoundedValue<i64>>, cmp_op: Compariso
                         if range.min.inclusivity == Inclusive {
                             (range.min.value, Exclusive)
                         } else {
-                            (range.min.value + 1.into(), Exclusive)
+                            (range.min.value.offset(1), Exclusive)
                         };
                     pr = Range::new(min_v, min_i,
                                     BoundedValue::Max, Inclusive);
@@ -197,7 +1245,7 @@ fn descend(node: &Expression, range: Range<BoundedValue<i64>>, cmp_op: Compariso
                         if range.max.inclusivity == Inclusive {
                             (range.max.value, Exclusive)
                         } else {
-                            (range.max.value + (-1).into(), Exclusive)
+                            (range.max.value.offset(-1), Exclusive)
                         };
                     pr = Range::new(range.min.value, range.min.inclusivity,
                                     BoundedValue::Max, Inclusive);
@@ -206,19 +1254,18 @@ fn descend(node: &Expression, range: Range<BoundedValue<i64>>, cmp_op: Compariso
                 },
                 Equals => {
                     pr = range;
-                    fr = Range::universe();
+                    fr = excluding(&e, range);
                 },
                 NotEquals => {
-                    pr = Range::universe();
+                    pr = excluding(&e, range);
                     fr = range;
                 }
             }
-            match (e.intersect_range(&pr).range(), e.intersect_range(&fr).range()) {
-                (Some(pass), Some(fail)) =>
-                    slices.push(VariableValueSlice {
-                        name: name.clone(), pass, fail,
-                    }),
-                _ => {},
+            if let (Some(pass), Some(fail)) =
+                (e.intersect_range(&pr).range(), e.intersect_range(&fr).range()) {
+                slices.push(VariableValueSlice {
+                    name: name.clone(), pass, fail,
+                });
             }
         },
         Expression::Binary { left, op, right } => {
@@ -254,69 +1301,218 @@ fn descend(node: &Expression, range: Range<BoundedValue<i64>>, cmp_op: Compariso
                     descend(right, l / range, cmp_op.flip(), variables, slices);
                 }
                 "%" => {
-                    // l % r < range
-                    // r < range
-                    descend(right, range, cmp_op, variables, slices);
+                    // l % r < range says nothing about r: 32 % 100 < 40
+                    let exact = match r.nonzero_magnitude() {
+                        Some((smallest, _)) => {
+                            let l = l.inclusive();
+                            l.min.value > -smallest && l.max.value < smallest
+                        },
+                        None => false,
+                    };
+                    if exact {
+                        // |l| < |r| so l % r == l
+                        descend(left, range, cmp_op, variables, slices);
+                    } else if remainder_bounds_dividend(range, cmp_op) {
+                        // l % r has the sign of l and |l % r| <= |l|, so
+                        // l % r > range >= 0 means l > range.  The converse
+                        // doesn't hold so the fail edge learns nothing.
+                        let mut dividend = Vec::new();
+                        descend(left, range, cmp_op, variables, &mut dividend);
+                        for slice in dividend {
+                            slices.push(VariableValueSlice { fail: Range::universe(), ..slice });
+                        }
+                    }
                 }
-                _ => unreachable!(),
+                // Nothing is learned about the operands of anything else
+                _ => {},
             }
         },
+        Unary { op, operand } => match op.as_str() {
+            // -x < range
+            // x > -range
+            "-" => descend(operand, -range, cmp_op.flip(), variables, slices),
+            "+" => descend(operand, range, cmp_op, variables, slices),
+            // ~x < range
+            // x > ~range
+            "~" => descend(operand, !range, cmp_op.flip(), variables, slices),
+            _ => {},
+        },
+        Call { .. } => {},
+        Index { .. } => {},
         Number(_) => {},
         Other => {},
     }
 }
 
-fn handle_comparison(location: &str, left: &Expression, cmp_op: &str, right: &Expression,
-                     variables: &HashMap<String, NumericalValue<BoundedValue<i64>>>,
-                     slices: &mut Vec<VariableValueSlice<BoundedValue<i64>>>,
-                     diagnostics: &mut Vec<Diagnostic>) {
+/// The values of `value` other than `range`, if `range` is a single value.
+/// Only a value at either end can be excluded from a `Range`, so this is
+/// `Range::universe()` if it isn't.
+fn excluding(value: &NumericalValue<BoundedValue<i64>>,
+             range: Range<BoundedValue<i64>>) -> Range<BoundedValue<i64>> {
+    let point = range.inclusive();
+    if point.min.value != point.max.value {
+        return Range::universe();
+    }
+    value.intersect(&NumericalValue::from(point).inverse()).range()
+        .unwrap_or_else(Range::universe)
+}
+
+/// Whether `l % r cmp_op range` implies `l cmp_op range`.
+fn remainder_bounds_dividend(range: Range<BoundedValue<i64>>, cmp_op: ComparisonOperator) -> bool {
     use self::ComparisonOperator::*;
-    let cmp_op = match cmp_op {
-        "<" => Less,
-        "<=" => LessEqual,
-        ">" => Greater,
-        ">=" => GreaterEqual,
-        "==" => Equals,
-        "!=" => NotEquals,
-        _ => unimplemented!(),
-    };
-    let l = parse_value_expression(left, variables);
-    let r = parse_value_expression(right, variables);
-    let l = l.range().unwrap();
-    let r = r.range().unwrap();
-    descend(left, r, cmp_op, variables, slices);
-    descend(right, l, cmp_op.flip(), variables, slices);
-    let always_true = match cmp_op {
-        Less => l.max < r.min,
-        LessEqual => l.max <= r.min,
-        Greater => l.min > r.max,
+    match cmp_op {
+        Greater => range.min.value >= 0,
+        GreaterEqual => range.min.value > 0,
+        Less => range.max.value <= 0,
+        LessEqual => range.max.value < 0,
+        Equals | NotEquals => false,
+    }
+}
+
+/// Splits `variables` into the states in which `left op right` passes and
+/// fails, `None` meaning it never does.  `&&` and `||` short circuit, so
+/// `right` is only refined in the states `left` lets through.
+fn refine_condition(location: &str, left: &Expression, op: &str, right: &Expression,
+                    variables: &Variables, diagnostics: &mut Vec<Diagnostic>)
+                    -> (Option<Variables>, Option<Variables>) {
+    if op == "&&" || op == "||" {
+        let mut parts = Vec::new();
+        let (left_pass, left_fail) = refine_operand(location, left, Some(variables), &mut parts);
+        let (pass, fail) = if op == "&&" {
+            let (right_pass, right_fail) =
+                refine_operand(location, right, left_pass.as_ref(), &mut parts);
+            (right_pass, join_states(left_fail, right_fail))
+        } else {
+            let (right_pass, right_fail) =
+                refine_operand(location, right, left_fail.as_ref(), &mut parts);
+            (join_states(left_pass, right_pass), right_fail)
+        };
+        // Whether a part is always true or false is reported as part of
+        // the whole condition
+        diagnostics.extend(parts.into_iter().filter(|diagnostic| {
+            !matches!(diagnostic.kind, DiagnosticKind::AlwaysTrue | DiagnosticKind::AlwaysFalse)
+        }));
+        match (&pass, &fail) {
+            (Some(_), None) =>
+                diagnostics.push(Diagnostic::new(location, DiagnosticKind::AlwaysTrue, None, None)),
+            (None, Some(_)) =>
+                diagnostics.push(Diagnostic::new(location, DiagnosticKind::AlwaysFalse, None, None)),
+            _ => {},
+        }
+        return (pass, fail);
+    }
+    if !is_condition(op) {
+        // Something like `x & 1`, which passes when it isn't zero
+        let value = Expression::Binary {
+            left: Box::new(left.clone()),
+            op: op.to_string(),
+            right: Box::new(right.clone()),
+        };
+        return refine_condition(location, &value, "!=", &Expression::Number(0),
+                                variables, diagnostics);
+    }
+
+    let mut variables = variables.clone();
+    check_division(location, left, &mut variables, diagnostics);
+    check_division(location, right, &mut variables, diagnostics);
+    check_indices(location, left, &mut variables, diagnostics);
+    check_indices(location, right, &mut variables, diagnostics);
+    let mut slices = Vec::new();
+    let outcome = handle_comparison(location, left, op, right, &variables,
+                                    &mut slices, diagnostics);
+    let branch = |pass: bool| {
+        if outcome == Some(!pass) {
+            return None;
+        }
+        let mut variables = variables.clone();
+        for slice in slices.iter() {
+            let value = parse_value_expression(&Expression::Identifier(slice.name.clone()),
+                                               &variables).intersect_range(
+                if pass { &slice.pass } else { &slice.fail });
+            // A variable with no possible values means the edge is never taken
+            value.range()?;
+            variables.insert(slice.name.clone(), value);
+        }
+        Some(variables)
+    };
+    (branch(true), branch(false))
+}
+
+/// Like `refine_condition`, for an operand of `&&`, `||` or `!` evaluated in
+/// `variables`.  Anything other than a condition passes when it isn't zero.
+fn refine_operand(location: &str, operand: &Expression, variables: Option<&Variables>,
+                  diagnostics: &mut Vec<Diagnostic>) -> (Option<Variables>, Option<Variables>) {
+    let variables = match variables {
+        Some(variables) => variables,
+        None => return (None, None),
+    };
+    match operand {
+        Expression::Binary { left, op, right } if is_condition(op) =>
+            refine_condition(location, left, op, right, variables, diagnostics),
+        Expression::Unary { op, operand } if op == "!" => {
+            let (pass, fail) = refine_operand(location, operand, Some(variables), diagnostics);
+            (fail, pass)
+        },
+        _ => refine_condition(location, operand, "!=", &Expression::Number(0),
+                              variables, diagnostics),
+    }
+}
+
+/// Returns the result of the comparison if it is always the same.
+fn handle_comparison(location: &str, left: &Expression, cmp_op: &str, right: &Expression,
+                     variables: &HashMap<String, NumericalValue<BoundedValue<i64>>>,
+                     slices: &mut Vec<VariableValueSlice<BoundedValue<i64>>>,
+                     diagnostics: &mut Vec<Diagnostic>) -> Option<bool> {
+    use self::ComparisonOperator::*;
+    let cmp_op = match cmp_op {
+        "<" => Less,
+        "<=" => LessEqual,
+        ">" => Greater,
+        ">=" => GreaterEqual,
+        "==" => Equals,
+        "!=" => NotEquals,
+        // `refine_condition` handles everything else before getting here
+        op => unreachable!("{} is not a comparison", op),
+    };
+    let left_value = parse_value_expression(left, variables);
+    let right_value = parse_value_expression(right, variables);
+    let l = left_value.range().unwrap();
+    let r = right_value.range().unwrap();
+    descend(left, r, cmp_op, variables, slices);
+    descend(right, l, cmp_op.flip(), variables, slices);
+    // Equal ranges only mean equal values when they hold a single value
+    let same_value = l == r && l.min.value == l.max.value;
+    let always_true = match cmp_op {
+        Less => l.max < r.min,
+        LessEqual => l.max <= r.min,
+        Greater => l.min > r.max,
         GreaterEqual => l.min >= r.max,
-        Equals => l == r,
+        Equals => same_value,
         NotEquals => l.max < r.min || l.min > r.max,
     };
     let always_false = match cmp_op {
-        Less => l.min > r.max,
-        LessEqual => l.min >= r.max,
-        Greater => l.max < r.min,
-        GreaterEqual => l.max <= r.min,
+        Less => l.min >= r.max,
+        LessEqual => l.min > r.max,
+        Greater => l.max <= r.min,
+        GreaterEqual => l.max < r.min,
         Equals => l.max < r.min || l.min > r.max,
-        NotEquals => l == r,
+        NotEquals => same_value,
     };
     if always_true {
-        diagnostics.push(Diagnostic {
-            location: location.to_string(),
-            always_true: true,
-        });
-    }
-    if always_false {
-        diagnostics.push(Diagnostic {
-            location: location.to_string(),
-            always_true: false,
-        });
+        diagnostics.push(Diagnostic::new(location, DiagnosticKind::AlwaysTrue,
+                                         Some(left_value), Some(right_value)));
+        Some(true)
+    } else if always_false {
+        diagnostics.push(Diagnostic::new(location, DiagnosticKind::AlwaysFalse,
+                                         Some(left_value), Some(right_value)));
+        Some(false)
+    } else {
+        None
     }
 }
 
 #[cfg(test)]
+#[allow(non_snake_case)]
 mod tests {
     use super::*;
 
@@ -694,18 +1890,61 @@ mod tests {
         let mut slices = Vec::new();
         let mut diagnostics = Vec::new();
         // 32 % a < 40
-        // a ∈ [0, 40)
+        // a could be anything, since 32 % 100 == 32
         handle_comparison("pos", &Binary { left: Box::new(Number(32)),
                                     op: "%".to_string(),
                                     right: Box::new(Identifier("a".to_string())) },
                           "<", &Number(40), &variables, &mut slices, &mut diagnostics);
+        assert!(slices.is_empty());
+        // 32 % a ∈ [0, 32]
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::AlwaysTrue)]);
+    }
+
+    #[test]
+    fn handle_comparison_13_2() {
+        use Expression::*;
+        let mut variables = HashMap::new();
+        variables.insert("a".to_string(), NumericalValue::new_value(
+            BoundedValue::Raw(0), Inclusivity::Inclusive,
+            BoundedValue::Raw(5), Inclusivity::Inclusive));
+        let mut slices = Vec::new();
+        let mut diagnostics = Vec::new();
+        // a % 8 < 5
+        // a < 5 since a is always smaller than 8
+        handle_comparison("pos", &Binary { left: Box::new(Identifier("a".to_string())),
+                                    op: "%".to_string(),
+                                    right: Box::new(Number(8)) },
+                          "<", &Number(5), &variables, &mut slices, &mut diagnostics);
         assert_eq!(slices,
                    vec![VariableValueSlice {
                        name: "a".to_string(),
-                       pass: Range::new(BoundedValue::Min, Inclusivity::Inclusive,
-                                        BoundedValue::Raw(40), Inclusivity::Exclusive),
-                       fail: Range::new(BoundedValue::Raw(40), Inclusivity::Inclusive,
+                       pass: Range::new(BoundedValue::Raw(0), Inclusivity::Inclusive,
+                                        BoundedValue::Raw(5), Inclusivity::Exclusive),
+                       fail: Range::new(BoundedValue::Raw(5), Inclusivity::Inclusive,
+                                        BoundedValue::Raw(5), Inclusivity::Inclusive),
+                   }]);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn handle_comparison_13_3() {
+        use Expression::*;
+        let mut variables = HashMap::new();
+        variables.insert("a".to_string(), NumericalValue::universe());
+        let mut slices = Vec::new();
+        let mut diagnostics = Vec::new();
+        // a % 8 > 2
+        // a > 2, but a <= 2 doesn't follow from a % 8 <= 2
+        handle_comparison("pos", &Binary { left: Box::new(Identifier("a".to_string())),
+                                    op: "%".to_string(),
+                                    right: Box::new(Number(8)) },
+                          ">", &Number(2), &variables, &mut slices, &mut diagnostics);
+        assert_eq!(slices,
+                   vec![VariableValueSlice {
+                       name: "a".to_string(),
+                       pass: Range::new(BoundedValue::Raw(2), Inclusivity::Exclusive,
                                         BoundedValue::Max, Inclusivity::Inclusive),
+                       fail: Range::universe(),
                    }]);
         assert!(diagnostics.is_empty());
     }
@@ -831,6 +2070,10 @@ mod tests {
         assert!(diagnostics.is_empty());
     }
 
+    fn kinds(diagnostics: &[Diagnostic]) -> Vec<(&str, DiagnosticKind)> {
+        diagnostics.iter().map(|d| (d.location.as_str(), d.kind)).collect()
+    }
+
     #[test]
     fn handle_comparison__creates_diagnostics_1() {
         use Expression::*;
@@ -843,11 +2086,7 @@ mod tests {
                           "<", &Identifier("b".to_string()),
                           &variables, &mut slices, &mut diagnostics);
         assert!(slices.is_empty());
-        assert_eq!(diagnostics,
-                   vec![Diagnostic {
-                       location: "pos".to_string(),
-                       message: "Expression is always true".to_string(),
-                   }]);
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::AlwaysTrue)]);
     }
 
     #[test]
@@ -862,11 +2101,7 @@ mod tests {
                           ">", &Identifier("b".to_string()),
                           &variables, &mut slices, &mut diagnostics);
         assert!(slices.is_empty());
-        assert_eq!(diagnostics,
-                   vec![Diagnostic {
-                       location: "pos".to_string(),
-                       message: "Expression is always false".to_string(),
-                   }]);
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::AlwaysFalse)]);
     }
 
     #[test]
@@ -885,11 +2120,7 @@ mod tests {
                           "<", &Identifier("b".to_string()),
                           &variables, &mut slices, &mut diagnostics);
         assert!(slices.is_empty());
-        assert_eq!(diagnostics,
-                   vec![Diagnostic {
-                       location: "pos".to_string(),
-                       message: "Expression is always true".to_string(),
-                   }]);
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::AlwaysTrue)]);
     }
 
     #[test]
@@ -908,11 +2139,81 @@ mod tests {
                           "==", &Identifier("b".to_string()),
                           &variables, &mut slices, &mut diagnostics);
         assert!(slices.is_empty());
-        assert_eq!(diagnostics,
-                   vec![Diagnostic {
-                       location: "pos".to_string(),
-                       message: "Expression is always false".to_string(),
-                   }]);
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::AlwaysFalse)]);
+    }
+
+    #[test]
+    fn handle_comparison__creates_diagnostics_touching() {
+        use Expression::*;
+        let mut variables = HashMap::new();
+        variables.insert("a".to_string(), NumericalValue::new_value(
+            BoundedValue::Raw(0), Inclusivity::Inclusive,
+            BoundedValue::Raw(10), Inclusivity::Inclusive));
+        let a = Identifier("a".to_string());
+        let cases = vec![("<", 0, Some(DiagnosticKind::AlwaysFalse)), ("<=", 0, None),
+                         (">", 10, Some(DiagnosticKind::AlwaysFalse)), (">=", 10, None),
+                         ("<", 11, Some(DiagnosticKind::AlwaysTrue)),
+                         (">=", 0, Some(DiagnosticKind::AlwaysTrue))];
+        for (op, number, kind) in cases {
+            let mut diagnostics = Vec::new();
+            handle_comparison("pos", &a, op, &Number(number), &variables,
+                              &mut Vec::new(), &mut diagnostics);
+            assert_eq!(diagnostics.first().map(|diagnostic| diagnostic.kind), kind, "a {} {}", op, number);
+        }
+    }
+
+    #[test]
+    fn check_division_1() {
+        use Expression::*;
+        let mut variables = HashMap::new();
+        variables.insert("a".to_string(), NumericalValue::new_value(
+            BoundedValue::Raw(-5), Inclusivity::Inclusive,
+            BoundedValue::Raw(5), Inclusivity::Inclusive));
+        let mut diagnostics = Vec::new();
+        // 10 / a
+        check_division("pos", &Binary { left: Box::new(Number(10)),
+                                        op: "/".to_string(),
+                                        right: Box::new(Identifier("a".to_string())) },
+                       &mut variables, &mut diagnostics);
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::PossibleDivisionByZero)]);
+        assert_eq!(format!("{:?}", variables["a"]), "[-5, 0) U (0, 5]");
+    }
+
+    #[test]
+    fn check_division_2() {
+        use Expression::*;
+        let mut variables = HashMap::new();
+        variables.insert("a".to_string(), NumericalValue::from(BoundedValue::Raw(0)));
+        let mut diagnostics = Vec::new();
+        // 3 + 10 % a
+        check_division("pos", &Binary { left: Box::new(Number(3)),
+                                        op: "+".to_string(),
+                                        right: Box::new(Binary {
+                                            left: Box::new(Number(10)),
+                                            op: "%".to_string(),
+                                            right: Box::new(Identifier("a".to_string())) }) },
+                       &mut variables, &mut diagnostics);
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::DivisionByZero)]);
+    }
+
+    #[test]
+    fn check_division_3() {
+        use Expression::*;
+        let mut variables = HashMap::new();
+        variables.insert("a".to_string(), NumericalValue::new_value(
+            BoundedValue::Raw(0), Inclusivity::Inclusive,
+            BoundedValue::Raw(5), Inclusivity::Inclusive));
+        let mut diagnostics = Vec::new();
+        // 10 / (a + 1)
+        check_division("pos", &Binary { left: Box::new(Number(10)),
+                                        op: "/".to_string(),
+                                        right: Box::new(Binary {
+                                            left: Box::new(Identifier("a".to_string())),
+                                            op: "+".to_string(),
+                                            right: Box::new(Number(1)) }) },
+                       &mut variables, &mut diagnostics);
+        assert!(diagnostics.is_empty());
+        assert_eq!(format!("{:?}", variables["a"]), "[0, 5]");
     }
 
     #[test]
@@ -922,14 +2223,18 @@ mod tests {
              NodeValue::VariableDeclaration {
                  declarations: vec![Declaration {
                      identifier: "a".to_string(),
-                     initializer: Expression::Other,
+                     initializer: Some(Expression::Other),
+                     integer_type: None,
+                     length: None,
                  }]
              }),
             ("b".to_string(),
              NodeValue::VariableDeclaration {
                  declarations: vec![Declaration {
                      identifier: "b".to_string(),
-                     initializer: Expression::Other,
+                     initializer: Some(Expression::Other),
+                     integer_type: None,
+                     length: None,
                  }]
              }),
             ("c".to_string(),
@@ -960,12 +2265,1013 @@ mod tests {
             ("g".to_string(), vec![]),
         ].into_iter().collect();
         let graph = Graph::new(values, successors, "a".to_string());
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&graph, &[], &[], &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(format!("{:?}", states["d"]["a"]), "[-inf, 13)");
+        assert_eq!(format!("{:?}", states["d"]["b"]), "[-inf, inf]");
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn overall_test_deep_chain() {
+        let count = 20000;
+        let mut values = HashMap::new();
+        let mut successors = HashMap::new();
+        for i in 0..count {
+            values.insert(i.to_string(), NodeValue::VariableAssignment {
+                left: Expression::Identifier("a".to_string()),
+                op: "=".to_string(),
+                right: Expression::Number(i),
+            });
+            successors.insert(i.to_string(), if i + 1 < count {
+                vec![Successor { key: (i + 1).to_string(), value: -1 }]
+            } else {
+                vec![]
+            });
+        }
+        let graph = Graph::new(values, successors, "0".to_string());
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&graph, &[], &[], &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(states.len(), count as usize);
+        assert_eq!(format!("{:?}", states[&(count - 1).to_string()]["a"]),
+                   format!("[{}, {}]", count - 2, count - 2));
+    }
+
+    #[test]
+    fn overall_test_diamonds() {
+        // 40 consecutive if/else diamonds, each joining back before the
+        // next one: 2^40 paths, but each node only needs a single visit.
+        let count = 40;
+        let mut values = HashMap::new();
+        let mut successors = HashMap::new();
+        values.insert("entry".to_string(), NodeValue::VariableDeclaration {
+            declarations: vec![Declaration {
+                identifier: "a".to_string(),
+                initializer: Some(Expression::Other),
+                integer_type: None,
+                length: None,
+            }]
+        });
+        successors.insert("entry".to_string(), vec![Successor { key: "c0".to_string(), value: -1 }]);
+        for i in 0..count {
+            let next = if i + 1 < count { format!("c{}", i + 1) } else { "exit".to_string() };
+            values.insert(format!("c{}", i), NodeValue::Comparison {
+                left: Expression::Identifier("a".to_string()),
+                op: "<".to_string(),
+                right: Expression::Number(i),
+            });
+            successors.insert(format!("c{}", i), vec![
+                Successor { key: format!("t{}", i), value: 1 },
+                Successor { key: format!("f{}", i), value: 0 },
+            ]);
+            values.insert(format!("t{}", i), NodeValue::Other);
+            successors.insert(format!("t{}", i), vec![Successor { key: next.clone(), value: -1 }]);
+            values.insert(format!("f{}", i), NodeValue::Other);
+            successors.insert(format!("f{}", i), vec![Successor { key: next, value: -1 }]);
+        }
+        values.insert("exit".to_string(), NodeValue::Other);
+        successors.insert("exit".to_string(), vec![]);
+        let graph = Graph::new(values, successors, "entry".to_string());
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&graph, &[], &[], &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(format!("{:?}", states["exit"]["a"]), "[-inf, inf]");
+        assert!(diagnostics.is_empty());
+    }
+
+    fn counting_loop(condition: Option<i64>, step: i64, integer_type: Option<IntegerType>) -> Graph {
+        // i = 0; while (i < condition) { i = i + step; }
+        let mut values = HashMap::new();
+        let mut successors = HashMap::new();
+        values.insert("init".to_string(), NodeValue::VariableDeclaration {
+            declarations: vec![Declaration {
+                identifier: "i".to_string(),
+                initializer: Some(Expression::Number(0)),
+                integer_type,
+                length: None,
+            }]
+        });
+        successors.insert("init".to_string(), vec![Successor { key: "head".to_string(), value: -1 }]);
+        match condition {
+            Some(bound) => {
+                values.insert("head".to_string(), NodeValue::Comparison {
+                    left: Expression::Identifier("i".to_string()),
+                    op: "<".to_string(),
+                    right: Expression::Number(bound),
+                });
+                successors.insert("head".to_string(), vec![
+                    Successor { key: "body".to_string(), value: 1 },
+                    Successor { key: "exit".to_string(), value: 0 },
+                ]);
+            },
+            None => {
+                values.insert("head".to_string(), NodeValue::Other);
+                successors.insert("head".to_string(), vec![
+                    Successor { key: "body".to_string(), value: -1 },
+                ]);
+            },
+        }
+        values.insert("body".to_string(), NodeValue::VariableAssignment {
+            left: Expression::Identifier("i".to_string()),
+            op: "=".to_string(),
+            right: Expression::Binary {
+                left: Box::new(Expression::Identifier("i".to_string())),
+                op: "+".to_string(),
+                right: Box::new(Expression::Number(step)),
+            },
+        });
+        successors.insert("body".to_string(), vec![Successor { key: "head".to_string(), value: -1 }]);
+        values.insert("exit".to_string(), NodeValue::Other);
+        successors.insert("exit".to_string(), vec![]);
+        Graph::new(values, successors, "init".to_string())
+    }
+
+    #[test]
+    fn overall_test_widening_unbounded_loop() {
+        let graph = counting_loop(None, 1, None);
+        assert_eq!(graph.loop_heads(), vec!["head"].into_iter().collect());
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&graph, &[], &[], &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(format!("{:?}", states["head"]["i"]), "[0, inf]");
+        assert_eq!(format!("{:?}", states["body"]["i"]), "[0, inf]");
+        assert!(!states.contains_key("exit"));
+    }
+
+    #[test]
+    fn overall_test_widening_threshold() {
+        let graph = counting_loop(Some(100), 1, None);
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&graph, &[], &[], &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(format!("{:?}", states["head"]["i"]), "[0, 101)");
+        assert_eq!(format!("{:?}", states["body"]["i"]), "[0, 100)");
+        assert_eq!(format!("{:?}", states["exit"]["i"]), "[100, 101)");
+        // i < 100 is always true on the first iteration, but not at the fixpoint
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn overall_test_narrowing() {
+        // The only thresholds are 0, 7 and 100, so widening overshoots
+        // i <= 106 and narrowing has to bring it back down.
+        let graph = counting_loop(Some(100), 7, None);
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&graph, &[], &[], &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(format!("{:?}", states["head"]["i"]), "[0, 107)");
+        assert_eq!(format!("{:?}", states["exit"]["i"]), "[100, 107)");
+    }
+
+    #[test]
+    fn overall_test_widening_gap() {
+        // x = 0; if (c == 0) x = 1000000000; while (x < 1000000000) x = x + 1;
+        let graph = parse_contents(r#"{"nodes": [
+            {"key": "init", "value": {"type": "variable_declaration", "declarations": [
+                {"identifier": "c", "initializer": null}, {"identifier": "x", "initializer": 0}]},
+             "successors": [{"key": "if", "value": -1}]},
+            {"key": "if", "value": {"type": "comparison", "left": "c", "op": "==", "right": 0},
+             "successors": [{"key": "big", "value": 1}, {"key": "head", "value": 0}]},
+            {"key": "big", "value": {"type": "variable_assignment", "left": "x", "right": 1000000000},
+             "successors": [{"key": "head", "value": -1}]},
+            {"key": "head", "value": {"type": "comparison", "left": "x", "op": "<", "right": 1000000000},
+             "successors": [{"key": "body", "value": 1}, {"key": "exit", "value": 0}]},
+            {"key": "body", "value": {"type": "variable_assignment", "left": "x",
+                                      "right": {"left": "x", "op": "+", "right": 1}},
+             "successors": [{"key": "head", "value": -1}]},
+            {"key": "exit", "value": {"type": "other"}, "successors": []}
+        ]}"#.to_string()).unwrap().functions.remove(0).graph;
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&graph, &[], &[], &mut Summaries::default(), &mut diagnostics);
+        // The gap between 0 and 1000000000 is widened away rather than
+        // filled in one iteration at a time
+        assert_eq!(format!("{:?}", states["exit"]["x"]), "[1000000000, 1000000001)");
+    }
+
+    #[test]
+    fn check_overflow_1() {
+        use Expression::*;
+        let mut types = Types::new();
+        types.insert("a".to_string(), IntegerType::U8);
         let mut variables = HashMap::new();
-        let mut history = HashMap::new();
         let mut diagnostics = Vec::new();
-        numerical_value_analysis(&graph, "a", &mut variables, &mut history, &mut diagnostics);
-        assert_eq!(format!("{:?}", history["d"]["a"]), "[-inf, 13)");
-        assert_eq!(format!("{:?}", history["d"]["b"]), "[-inf, inf]");
+        assign("pos", "a", &Number(256), &mut variables, &types, &mut diagnostics);
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::Overflow)]);
+        assert_eq!(format!("{:?}", variables["a"]), "[0, 255]");
+    }
+
+    #[test]
+    fn check_overflow_2() {
+        use Expression::*;
+        let mut types = Types::new();
+        types.insert("a".to_string(), IntegerType::I8);
+        let mut variables = HashMap::new();
+        variables.insert("b".to_string(), NumericalValue::new_value(
+            BoundedValue::Raw(100), Inclusivity::Inclusive,
+            BoundedValue::Raw(200), Inclusivity::Inclusive));
+        let mut diagnostics = Vec::new();
+        assign("pos", "a", &Identifier("b".to_string()), &mut variables, &types, &mut diagnostics);
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::PossibleOverflow)]);
+        assert_eq!(format!("{:?}", variables["a"]), "[-128, 127]");
+
+        diagnostics.clear();
+        assign("pos", "a", &Number(127), &mut variables, &types, &mut diagnostics);
+        assert!(diagnostics.is_empty());
+        assert_eq!(format!("{:?}", variables["a"]), "[127, 127]");
+    }
+
+    #[test]
+    fn check_overflow_3() {
+        let mut types = Types::new();
+        types.insert("a".to_string(), IntegerType::U16);
+        let mut variables = HashMap::new();
+        let mut diagnostics = Vec::new();
+        assign("pos", "a", &Expression::Other, &mut variables, &types, &mut diagnostics);
+        assert!(diagnostics.is_empty());
+        assert_eq!(format!("{:?}", variables["a"]), "[0, 65535]");
+
+        assign("pos", "b", &Expression::Other, &mut variables, &types, &mut diagnostics);
         assert!(diagnostics.is_empty());
+        assert_eq!(format!("{:?}", variables["b"]), "[-inf, inf]");
+    }
+
+    #[test]
+    fn overall_test_overflow_loop() {
+        let graph = counting_loop(None, 1, Some(IntegerType::U8));
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&graph, &[], &[], &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(format!("{:?}", states["head"]["i"]), "[0, 255]");
+        assert_eq!(kinds(&diagnostics), vec![("body", DiagnosticKind::PossibleOverflow),
+                                             ("exit", DiagnosticKind::Unreachable)]);
+    }
+
+    #[test]
+    fn transfer_describes_diagnostics() {
+        use Expression::*;
+        let mut variables = HashMap::new();
+        variables.insert("a".to_string(), NumericalValue::new_value(
+            BoundedValue::Raw(2), Inclusivity::Inclusive,
+            BoundedValue::Raw(7), Inclusivity::Exclusive));
+        let node = NodeValue::Comparison {
+            left: Identifier("a".to_string()),
+            op: "<".to_string(),
+            right: Number(10),
+        };
+        let mut diagnostics = Vec::new();
+        transfer("pos", &node, &State::from(variables.clone()), &[], &Types::new(),
+                 &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(diagnostics,
+                   vec![Diagnostic {
+                       function: String::new(),
+                       location: "pos".to_string(),
+                       kind: DiagnosticKind::AlwaysTrue,
+                       severity: Severity::Warning,
+                       message: "comparison is always true: `a < 10`".to_string(),
+                       left: Some(variables["a"].clone()),
+                       right: Some(NumericalValue::from(BoundedValue::Raw(10))),
+                   }]);
+        assert_eq!(serde_json::to_string(&diagnostics[0]).unwrap(),
+                   "{\"function\":\"\",\"location\":\"pos\",\"kind\":\"always_true\",\"severity\":\"warning\",\
+                    \"message\":\"comparison is always true: `a < 10`\",\
+                    \"left\":\"[2, 7)\",\"right\":\"[10, 10]\"}");
+    }
+
+    #[test]
+    fn overall_test_unreachable() {
+        // a = ?; if (a < 5) { if (a > 10) { dead } } else { if (a < 7) { live } }
+        let values = vec![
+            ("a".to_string(),
+             NodeValue::VariableDeclaration {
+                 declarations: vec![Declaration {
+                     identifier: "a".to_string(),
+                     initializer: Some(Expression::Other),
+                     integer_type: None,
+                     length: None,
+                 }]
+             }),
+            ("b".to_string(),
+             NodeValue::Comparison {
+                 left: Expression::Identifier("a".to_string()),
+                 op: "<".to_string(),
+                 right: Expression::Number(5)
+             }),
+            ("c".to_string(),
+             NodeValue::Comparison {
+                 left: Expression::Identifier("a".to_string()),
+                 op: ">".to_string(),
+                 right: Expression::Number(10)
+             }),
+            ("dead".to_string(), NodeValue::Other),
+            ("d".to_string(),
+             NodeValue::Comparison {
+                 left: Expression::Identifier("a".to_string()),
+                 op: "<".to_string(),
+                 right: Expression::Number(7)
+             }),
+            ("live".to_string(), NodeValue::Other),
+            ("end".to_string(), NodeValue::Other),
+            ("orphan".to_string(), NodeValue::Other),
+        ].into_iter().collect();
+        let successors = vec![
+            ("a".to_string(), vec![Successor { key: "b".to_string(), value: -1 }]),
+            ("b".to_string(), vec![Successor { key: "c".to_string(), value: 1 },
+                                   Successor { key: "d".to_string(), value: 0 }]),
+            ("c".to_string(), vec![Successor { key: "dead".to_string(), value: 1 },
+                                   Successor { key: "end".to_string(), value: 0 }]),
+            ("dead".to_string(), vec![Successor { key: "end".to_string(), value: -1 }]),
+            ("d".to_string(), vec![Successor { key: "live".to_string(), value: 1 },
+                                   Successor { key: "end".to_string(), value: 0 }]),
+            ("live".to_string(), vec![Successor { key: "end".to_string(), value: -1 }]),
+            ("end".to_string(), vec![]),
+            ("orphan".to_string(), vec![Successor { key: "end".to_string(), value: -1 }]),
+        ].into_iter().collect();
+        let graph = Graph::new(values, successors, "a".to_string());
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&graph, &[], &[], &mut Summaries::default(), &mut diagnostics);
+        assert!(!states.contains_key("dead"));
+        assert_eq!(format!("{:?}", states["live"]["a"]), "[5, 7)");
+        assert_eq!(format!("{:?}", states["end"]["a"]), "[-inf, inf]");
+        assert_eq!(kinds(&diagnostics), vec![("c", DiagnosticKind::AlwaysFalse),
+                                             ("dead", DiagnosticKind::Unreachable),
+                                             ("orphan", DiagnosticKind::Unreachable)]);
+        assert_eq!(diagnostics[1].message, "unreachable code: `__other_stmt();`");
+    }
+
+    fn condition_variables() -> Variables {
+        let mut variables = HashMap::new();
+        variables.insert("a".to_string(), NumericalValue::new_value(
+            BoundedValue::Raw(0), Inclusivity::Inclusive,
+            BoundedValue::Raw(20), Inclusivity::Inclusive));
+        variables.insert("b".to_string(), NumericalValue::new_value(
+            BoundedValue::Raw(0), Inclusivity::Inclusive,
+            BoundedValue::Raw(5), Inclusivity::Inclusive));
+        variables
+    }
+
+    fn compare(left: Expression, op: &str, right: Expression) -> Expression {
+        Expression::Binary { left: Box::new(left), op: op.to_string(), right: Box::new(right) }
+    }
+
+    #[test]
+    fn refine_condition_and() {
+        use Expression::*;
+        let mut diagnostics = Vec::new();
+        // a < 10 && a > 3
+        let (pass, fail) = refine_condition(
+            "pos", &compare(Identifier("a".to_string()), "<", Number(10)), "&&",
+            &compare(Identifier("a".to_string()), ">", Number(3)),
+            &condition_variables(), &mut diagnostics);
+        assert_eq!(format!("{:?}", pass.unwrap()["a"]), "(3, 10)");
+        assert_eq!(format!("{:?}", fail.unwrap()["a"]), "[0, 3] U [10, 20]");
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn refine_condition_or() {
+        use Expression::*;
+        let mut diagnostics = Vec::new();
+        // a < 5 || a > 15
+        let (pass, fail) = refine_condition(
+            "pos", &compare(Identifier("a".to_string()), "<", Number(5)), "||",
+            &compare(Identifier("a".to_string()), ">", Number(15)),
+            &condition_variables(), &mut diagnostics);
+        assert_eq!(format!("{:?}", pass.unwrap()["a"]), "[0, 5) U (15, 20]");
+        assert_eq!(format!("{:?}", fail.unwrap()["a"]), "[5, 15]");
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn refine_condition_not() {
+        use Expression::*;
+        let mut diagnostics = Vec::new();
+        // !(a < 5) && a < 8
+        let (pass, fail) = refine_condition(
+            "pos",
+            &Unary { op: "!".to_string(),
+                     operand: Box::new(compare(Identifier("a".to_string()), "<", Number(5))) },
+            "&&", &compare(Identifier("a".to_string()), "<", Number(8)),
+            &condition_variables(), &mut diagnostics);
+        assert_eq!(format!("{:?}", pass.unwrap()["a"]), "[5, 8)");
+        assert_eq!(format!("{:?}", fail.unwrap()["a"]), "[0, 5) U [8, 20]");
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn refine_condition_always_true() {
+        use Expression::*;
+        let mut diagnostics = Vec::new();
+        // a >= 0 || b < 3
+        let (pass, fail) = refine_condition(
+            "pos", &compare(Identifier("a".to_string()), ">=", Number(0)), "||",
+            &compare(Identifier("b".to_string()), "<", Number(3)),
+            &condition_variables(), &mut diagnostics);
+        assert_eq!(format!("{:?}", pass.unwrap()["a"]), "[0, 20]");
+        assert!(fail.is_none());
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::AlwaysTrue)]);
+    }
+
+    #[test]
+    fn refine_condition_short_circuit() {
+        use Expression::*;
+        let mut diagnostics = Vec::new();
+        // b && 10 / b > 1
+        let (pass, fail) = refine_condition(
+            "pos", &Identifier("b".to_string()), "&&",
+            &compare(compare(Number(10), "/", Identifier("b".to_string())), ">", Number(1)),
+            &condition_variables(), &mut diagnostics);
+        assert_eq!(format!("{:?}", pass.unwrap()["b"]), "(0, 5]");
+        assert_eq!(format!("{:?}", fail.unwrap()["b"]), "[0, 0]");
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn parse_value_expression_conditions() {
+        use Expression::*;
+        let variables = condition_variables();
+        assert_eq!(format!("{:?}", parse_value_expression(
+            &compare(Identifier("a".to_string()), "<", Number(3)), &variables)), "[0, 1]");
+        assert_eq!(format!("{:?}", parse_value_expression(
+            &Unary { op: "!".to_string(), operand: Box::new(Number(0)) }, &variables)), "[1, 1]");
+        assert_eq!(format!("{:?}", parse_value_expression(
+            &Unary { op: "!".to_string(), operand: Box::new(Identifier("b".to_string())) },
+            &variables)), "[0, 1]");
+    }
+
+    #[test]
+    fn parse_value_expression_unary() {
+        use Expression::*;
+        let variables = condition_variables();
+        let unary = |op: &str, operand| Unary { op: op.to_string(), operand: Box::new(operand) };
+        assert_eq!(format!("{:?}", parse_value_expression(
+            &unary("-", Identifier("a".to_string())), &variables)), "[-20, 0]");
+        assert_eq!(format!("{:?}", parse_value_expression(
+            &unary("+", Identifier("a".to_string())), &variables)), "[0, 20]");
+        assert_eq!(format!("{:?}", parse_value_expression(
+            &unary("~", Identifier("b".to_string())), &variables)), "[-6, -1]");
+    }
+
+    #[test]
+    fn handle_comparison_unary() {
+        use Expression::*;
+        let variables = condition_variables();
+        let mut slices = Vec::new();
+        let mut diagnostics = Vec::new();
+        // -a < -5
+        handle_comparison("pos", &Unary { op: "-".to_string(),
+                                          operand: Box::new(Identifier("a".to_string())) },
+                          "<", &Number(-5), &variables, &mut slices, &mut diagnostics);
+        // ~b >= -3
+        handle_comparison("pos", &Unary { op: "~".to_string(),
+                                          operand: Box::new(Identifier("b".to_string())) },
+                          ">=", &Number(-3), &variables, &mut slices, &mut diagnostics);
+        assert_eq!(slices,
+                   vec![VariableValueSlice {
+                       name: "a".to_string(),
+                       pass: Range::new(BoundedValue::Raw(5), Inclusivity::Exclusive,
+                                        BoundedValue::Raw(20), Inclusivity::Inclusive),
+                       fail: Range::new(BoundedValue::Raw(0), Inclusivity::Inclusive,
+                                        BoundedValue::Raw(5), Inclusivity::Inclusive),
+                   }, VariableValueSlice {
+                       name: "b".to_string(),
+                       pass: Range::new(BoundedValue::Raw(0), Inclusivity::Inclusive,
+                                        BoundedValue::Raw(2), Inclusivity::Inclusive),
+                       fail: Range::new(BoundedValue::Raw(2), Inclusivity::Exclusive,
+                                        BoundedValue::Raw(5), Inclusivity::Inclusive),
+                   }]);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn parse_value_expression_bitwise() {
+        use Expression::*;
+        let variables = condition_variables();
+        assert_eq!(format!("{:?}", parse_value_expression(
+            &compare(Other, "&", Number(0xff)), &variables)), "[0, 255]");
+        assert_eq!(format!("{:?}", parse_value_expression(
+            &compare(Identifier("b".to_string()), "<<", Number(2)), &variables)), "[0, 20]");
+        assert_eq!(format!("{:?}", parse_value_expression(
+            &compare(Identifier("a".to_string()), ">>", Identifier("b".to_string())), &variables)),
+            "[0, 20]");
+        assert_eq!(format!("{:?}", parse_value_expression(
+            &compare(Identifier("a".to_string()), "**", Number(2)), &variables)), "[-inf, inf]");
+    }
+
+    #[test]
+    fn refine_condition_value() {
+        use Expression::*;
+        let mut diagnostics = Vec::new();
+        // if (a & 0)
+        let (pass, fail) = refine_condition("pos", &Identifier("a".to_string()), "&", &Number(0),
+                                            &condition_variables(), &mut diagnostics);
+        assert!(pass.is_none());
+        assert!(fail.is_some());
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::AlwaysFalse)]);
+    }
+
+    #[test]
+    fn overall_test_compound_assignment() {
+        let graph = parse_contents(r#"{"nodes": [
+            {"key": "a", "value": {"type": "variable_declaration",
+                                   "declarations": [{"identifier": "i", "initializer": 3}]},
+             "successors": [{"key": "b", "value": -1}]},
+            {"key": "b", "value": {"type": "variable_assignment", "left": "i", "op": "*=", "right": 4},
+             "successors": [{"key": "c", "value": -1}]},
+            {"key": "c", "value": {"type": "variable_assignment", "left": "i", "op": "<<=", "right": 1},
+             "successors": [{"key": "d", "value": -1}]},
+            {"key": "d", "value": {"type": "increment", "identifier": "i", "op": "--", "prefix": true},
+             "successors": [{"key": "e", "value": -1}]},
+            {"key": "e", "value": {"type": "increment", "identifier": "i", "op": "++"},
+             "successors": [{"key": "f", "value": -1}]},
+            {"key": "f", "value": {"type": "variable_assignment", "left": "i", "right": 7},
+             "successors": [{"key": "g", "value": -1}]},
+            {"key": "g", "value": {"type": "other"}, "successors": []}
+        ]}"#.to_string()).unwrap().functions.remove(0).graph;
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&graph, &[], &[], &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(format!("{:?}", states["c"]["i"]), "[12, 12]");
+        assert_eq!(format!("{:?}", states["d"]["i"]), "[24, 24]");
+        assert_eq!(format!("{:?}", states["e"]["i"]), "[23, 23]");
+        assert_eq!(format!("{:?}", states["f"]["i"]), "[24, 24]");
+        assert_eq!(format!("{:?}", states["g"]["i"]), "[7, 7]");
+        assert_eq!(graph.value_of("d").unwrap().to_string(), "--i;");
+        assert_eq!(graph.value_of("e").unwrap().to_string(), "i++;");
+        assert_eq!(graph.value_of("f").unwrap().to_string(), "i = 7;");
+    }
+
+    #[test]
+    fn analyze_functions() {
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "a", "parameters": ["x"], "nodes": [
+                {"key": "a", "value": {"type": "variable_declaration",
+                                       "declarations": [{"identifier": "y", "initializer": {
+                                           "left": 10, "op": "/", "right": "x"}}]},
+                 "successors": [{"key": "b", "value": -1}]},
+                {"key": "b", "value": {"type": "other"}, "successors": []}
+            ]},
+            {"name": "g", "entry": "a", "nodes": [
+                {"key": "a", "value": {"type": "comparison", "left": 1, "op": "<", "right": 2},
+                 "successors": [{"key": "b", "value": 1}, {"key": "b", "value": 0}]},
+                {"key": "b", "value": {"type": "other"}, "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let diagnostics = analyze(&program, false).diagnostics;
+        assert_eq!(diagnostics.iter().map(|d| (d.function.as_str(), d.location.as_str(), d.kind))
+                   .collect::<Vec<_>>(),
+                   vec![("f", "a", DiagnosticKind::PossibleDivisionByZero),
+                        ("g", "a", DiagnosticKind::AlwaysTrue)]);
+    }
+
+    #[test]
+    fn overall_test_parameters() {
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "a",
+             "parameters": [{"name": "n", "min": 0, "max": 100}, {"name": "c", "type": "u8"}, "x"],
+             "nodes": [
+                {"key": "a", "value": {"type": "variable_assignment", "left": "c", "op": "+=", "right": "n"},
+                 "successors": [{"key": "b", "value": -1}]},
+                {"key": "b", "value": {"type": "comparison", "left": "g", "op": "<", "right": "x"},
+                 "successors": [{"key": "c", "value": 1}, {"key": "c", "value": 0}]},
+                {"key": "c", "value": {"type": "other"}, "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let function = &program.functions[0];
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&function.graph, &function.parameters, &[],
+                                              &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(format!("{:?}", states["a"]["n"]), "[0, 100]");
+        assert_eq!(format!("{:?}", states["a"]["c"]), "[0, 255]");
+        assert_eq!(format!("{:?}", states["a"]["x"]), "[-inf, inf]");
+        assert_eq!(format!("{:?}", states["b"]["c"]), "[0, 255]");
+        // g comes from outside the function, so it could hold anything
+        assert_eq!(format!("{:?}", states["c"]["g"]), "[-inf, inf]");
+        assert_eq!(kinds(&diagnostics), vec![("a", DiagnosticKind::PossibleOverflow)]);
+    }
+
+    #[test]
+    fn overall_test_uninitialized() {
+        // int x; if (a < 0) { x = 1; } y = x; z = x;
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "a", "parameters": ["a"], "nodes": [
+                {"key": "decl", "value": {"type": "variable_declaration",
+                                          "declarations": [{"identifier": "x"}]},
+                 "successors": [{"key": "if", "value": -1}]},
+                {"key": "a", "value": {"type": "other"}, "successors": [{"key": "decl", "value": -1}]},
+                {"key": "if", "value": {"type": "comparison", "left": "a", "op": "<", "right": 0},
+                 "successors": [{"key": "then", "value": 1}, {"key": "y", "value": 0}]},
+                {"key": "then", "value": {"type": "variable_assignment", "left": "x", "right": 1},
+                 "successors": [{"key": "y", "value": -1}]},
+                {"key": "y", "value": {"type": "variable_assignment", "left": "y", "right": "x"},
+                 "successors": [{"key": "z", "value": -1}]},
+                {"key": "z", "value": {"type": "variable_assignment", "left": "z", "right": "x"},
+                 "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let function = &program.functions[0];
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&function.graph, &function.parameters, &[],
+                                              &mut Summaries::default(), &mut diagnostics);
+        assert!(!states["if"].variables.contains_key("x"));
+        assert_eq!(format!("{:?}", states["y"]["x"]), "[1, 1]");
+        assert!(states["y"].uninitialized.contains("x"));
+        // Reported once, then x is assumed to hold anything
+        assert_eq!(format!("{:?}", states["z"]["x"]), "[-inf, inf]");
+        assert!(states["z"].uninitialized.is_empty());
+        assert_eq!(kinds(&diagnostics), vec![("y", DiagnosticKind::PossiblyUninitialized)]);
+        assert_eq!(diagnostics[0].message, "use of possibly uninitialized variable: `y = x;`");
+    }
+
+    #[test]
+    fn overall_test_never_initialized() {
+        // int x; y = x; z = g;
+        let graph = parse_contents(r#"{"nodes": [
+            {"key": "decl", "value": {"type": "variable_declaration",
+                                      "declarations": [{"identifier": "x"}]},
+             "successors": [{"key": "y", "value": -1}]},
+            {"key": "y", "value": {"type": "variable_assignment", "left": "y", "right": "x"},
+             "successors": [{"key": "z", "value": -1}]},
+            {"key": "z", "value": {"type": "variable_assignment", "left": "z", "right": "g"},
+             "successors": []}
+        ]}"#.to_string()).unwrap().functions.remove(0).graph;
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&graph, &[], &[], &mut Summaries::default(), &mut diagnostics);
+        assert!(states["y"].uninitialized.contains("x"));
+        assert_eq!(format!("{:?}", states["z"]["y"]), "[-inf, inf]");
+        // Only x is declared here
+        assert_eq!(kinds(&diagnostics), vec![("y", DiagnosticKind::Uninitialized)]);
+    }
+
+    #[test]
+    fn overall_test_calls() {
+        // int clamp(v, lo, hi) { if (v < lo) return lo; if (v > hi) return hi; return v; }
+        // r = clamp(x, 0, 10); if (r > 10) {}
+        let program = parse_contents(r#"{"functions": [
+            {"name": "clamp", "entry": "low", "parameters": ["v", "lo", "hi"], "nodes": [
+                {"key": "low", "value": {"type": "comparison", "left": "v", "op": "<", "right": "lo"},
+                 "successors": [{"key": "ret_lo", "value": 1}, {"key": "high", "value": 0}]},
+                {"key": "ret_lo", "value": {"type": "return", "value": "lo"}, "successors": []},
+                {"key": "high", "value": {"type": "comparison", "left": "v", "op": ">", "right": "hi"},
+                 "successors": [{"key": "ret_hi", "value": 1}, {"key": "ret_v", "value": 0}]},
+                {"key": "ret_hi", "value": {"type": "return", "value": "hi"}, "successors": []},
+                {"key": "ret_v", "value": {"type": "return", "value": "v"}, "successors": []}
+            ]},
+            {"name": "main", "entry": "r", "parameters": ["x"], "nodes": [
+                {"key": "r", "value": {"type": "variable_assignment", "left": "r",
+                                       "right": {"function": "clamp", "arguments": ["x", 0, 10]}},
+                 "successors": [{"key": "if", "value": -1}]},
+                {"key": "if", "value": {"type": "comparison", "left": "r", "op": ">", "right": 10},
+                 "successors": [{"key": "end", "value": 1}, {"key": "end", "value": 0}]},
+                {"key": "end", "value": {"type": "other"}, "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let function = &program.functions[1];
+        let mut summaries = Summaries::new(&program);
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&function.graph, &function.parameters, &[],
+                                              &mut summaries, &mut diagnostics);
+        assert_eq!(format!("{:?}", states["if"]["r"]), "[0, 10]");
+        assert!(states["if"].variables.keys().all(|name| !name.starts_with(TEMPORARY_PREFIX)));
+        assert_eq!(kinds(&diagnostics), vec![("if", DiagnosticKind::AlwaysFalse)]);
+    }
+
+    #[test]
+    fn overall_test_calls_printed_alike() {
+        // int id(a) { return a; }  x = id((1 + 2) * 3) - id(1 + (2 * 3));
+        let program = parse_contents(r#"{"functions": [
+            {"name": "id", "entry": "ret", "parameters": ["a"], "nodes": [
+                {"key": "ret", "value": {"type": "return", "value": "a"}, "successors": []}
+            ]},
+            {"name": "main", "entry": "x", "nodes": [
+                {"key": "x", "value": {"type": "variable_assignment", "left": "x", "right": {
+                    "left": {"function": "id", "arguments": [
+                        {"left": {"left": 1, "op": "+", "right": 2}, "op": "*", "right": 3}]},
+                    "op": "-",
+                    "right": {"function": "id", "arguments": [
+                        {"left": 1, "op": "+", "right": {"left": 2, "op": "*", "right": 3}}]}}},
+                 "successors": [{"key": "end", "value": -1}]},
+                {"key": "end", "value": {"type": "other"}, "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let function = &program.functions[1];
+        let mut summaries = Summaries::new(&program);
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&function.graph, &function.parameters, &[],
+                                              &mut summaries, &mut diagnostics);
+        assert_eq!(format!("{:?}", states["end"]["x"]), "[2, 2]");
+    }
+
+    #[test]
+    fn overall_test_recursion() {
+        // int f(n) { return f(n - 1); }  r = f(3);
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "ret", "parameters": ["n"], "nodes": [
+                {"key": "ret", "value": {"type": "return", "value": {
+                    "function": "f", "arguments": [{"left": "n", "op": "-", "right": 1}]}},
+                 "successors": []}
+            ]},
+            {"name": "main", "entry": "r", "nodes": [
+                {"key": "r", "value": {"type": "variable_declaration", "declarations": [
+                    {"identifier": "r", "initializer": {"function": "f", "arguments": [3]}}]},
+                 "successors": [{"key": "end", "value": -1}]},
+                {"key": "end", "value": {"type": "call", "function": "g", "arguments": ["r"]},
+                 "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let function = &program.functions[1];
+        let mut summaries = Summaries::new(&program);
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&function.graph, &function.parameters, &[],
+                                              &mut summaries, &mut diagnostics);
+        assert_eq!(format!("{:?}", states["end"]["r"]), "[-inf, inf]");
+        assert!(diagnostics.is_empty());
+        assert_eq!(program.functions[0].graph.value_of("ret").unwrap().to_string(),
+                   "return f(n - 1);");
+    }
+
+    #[test]
+    fn analyze_returns() {
+        // int sign(x) { if (x < 0) return -1; if (x > 0) return 1; return 0; }
+        // void spin() { while (1) {} }
+        let program = parse_contents(r#"{"functions": [
+            {"name": "sign", "entry": "neg", "parameters": ["x"], "nodes": [
+                {"key": "neg", "value": {"type": "comparison", "left": "x", "op": "<", "right": 0},
+                 "successors": [{"key": "ret_neg", "value": 1}, {"key": "pos", "value": 0}]},
+                {"key": "ret_neg", "value": {"type": "return", "value": {"op": "-", "operand": 1}},
+                 "successors": []},
+                {"key": "pos", "value": {"type": "comparison", "left": "x", "op": ">", "right": 0},
+                 "successors": [{"key": "ret_pos", "value": 1}, {"key": "ret_zero", "value": 0}]},
+                {"key": "ret_pos", "value": {"type": "return", "value": 1}, "successors": []},
+                {"key": "ret_zero", "value": {"type": "return", "value": "x"}, "successors": []}
+            ]},
+            {"name": "spin", "entry": "loop", "nodes": [
+                {"key": "loop", "value": {"type": "other"}, "successors": [{"key": "loop", "value": -1}]}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let report = analyze(&program, false);
+        assert!(report.diagnostics.is_empty());
+        assert_eq!(report.functions.len(), 2);
+        assert_eq!(report.functions[0].name, "sign");
+        assert_eq!(format!("{:?}", report.functions[0].returns.as_ref().unwrap()),
+                   "[-1, -1] U [0, 0] U [1, 1]");
+        assert_eq!(report.functions[1].name, "spin");
+        assert!(report.functions[1].returns.is_none());
+        assert_eq!(serde_json::to_string(&report.functions[1]).unwrap(),
+                   r#"{"name":"spin","returns":null}"#);
+    }
+
+    #[test]
+    fn overall_test_arrays() {
+        // u8 buf[n]; x = buf[10]; for (i = 0; i < 20; i++) { buf[i] = i; y = buf[i - 1]; }
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "decl", "parameters": [{"name": "n", "min": 1, "max": 10}], "nodes": [
+                {"key": "decl", "value": {"type": "variable_declaration", "declarations": [
+                    {"identifier": "buf", "type": "u8", "length": "n"},
+                    {"identifier": "i", "initializer": 0}]},
+                 "successors": [{"key": "x", "value": -1}]},
+                {"key": "x", "value": {"type": "variable_assignment", "left": "x",
+                                       "right": {"array": "buf", "index": 10}},
+                 "successors": [{"key": "head", "value": -1}]},
+                {"key": "head", "value": {"type": "comparison", "left": "i", "op": "<", "right": 20},
+                 "successors": [{"key": "body", "value": 1}, {"key": "exit", "value": 0}]},
+                {"key": "body", "value": {"type": "variable_assignment",
+                                          "left": {"array": "buf", "index": "i"}, "right": "i"},
+                 "successors": [{"key": "y", "value": -1}]},
+                {"key": "y", "value": {"type": "variable_assignment", "left": "y",
+                                       "right": {"array": "buf", "index": {"left": "i", "op": "-", "right": 1}}},
+                 "successors": [{"key": "step", "value": -1}]},
+                {"key": "step", "value": {"type": "increment", "identifier": "i", "op": "++"},
+                 "successors": [{"key": "head", "value": -1}]},
+                {"key": "exit", "value": {"type": "other"}, "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let function = &program.functions[0];
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&function.graph, &function.parameters, &[],
+                                              &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(format!("{:?}", states["head"][&length_of("buf")]), "[1, 10]");
+        assert_eq!(format!("{:?}", states["head"]["buf"]), "[0, 255]");
+        // Only indices below the length get past the store
+        assert_eq!(format!("{:?}", states["step"]["i"]), "[0, 10)");
+        assert_eq!(format!("{:?}", states["head"]["i"]), "[0, 11)");
+        // So the loop never gets to i = 20
+        assert_eq!(kinds(&diagnostics), vec![("x", DiagnosticKind::OutOfBounds),
+                                             ("head", DiagnosticKind::AlwaysTrue),
+                                             ("body", DiagnosticKind::PossibleOutOfBounds),
+                                             ("y", DiagnosticKind::PossibleOutOfBounds),
+                                             ("exit", DiagnosticKind::Unreachable)]);
+        assert_eq!(diagnostics[0].message, "index is definitely out of bounds: `x = buf[10];`");
+    }
+
+    #[test]
+    fn check_indices_in_bounds() {
+        use Expression::*;
+        let mut variables = HashMap::new();
+        variables.insert(length_of("a"), NumericalValue::from(BoundedValue::Raw(4)));
+        variables.insert("i".to_string(), NumericalValue::new_value(
+            BoundedValue::Raw(0), Inclusivity::Inclusive,
+            BoundedValue::Raw(3), Inclusivity::Inclusive));
+        let mut diagnostics = Vec::new();
+        check_indices("pos", &Index { array: "a".to_string(), index: Box::new(Identifier("i".to_string())) },
+                      &mut variables, &mut diagnostics);
+        check_indices("pos", &Index { array: "a".to_string(), index: Box::new(Number(-1)) },
+                      &mut variables, &mut diagnostics);
+        // The length of `b` is unknown
+        check_indices("pos", &Index { array: "b".to_string(), index: Box::new(Number(-1)) },
+                      &mut variables, &mut diagnostics);
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::OutOfBounds)]);
+    }
+
+    #[test]
+    fn check_indices_gap() {
+        use Expression::*;
+        let mut variables = HashMap::new();
+        variables.insert(length_of("buf"), NumericalValue::from(BoundedValue::Raw(10)));
+        let i = NumericalValue::from(BoundedValue::Raw(-5))
+            .union(&NumericalValue::from(BoundedValue::Raw(20)));
+        variables.insert("i".to_string(), i.clone());
+        let mut diagnostics = Vec::new();
+        // None of i fits in buf
+        check_indices("pos", &Index { array: "buf".to_string(), index: Box::new(Identifier("i".to_string())) },
+                      &mut variables, &mut diagnostics);
+        assert_eq!(kinds(&diagnostics), vec![("pos", DiagnosticKind::OutOfBounds)]);
+        assert_eq!(variables["i"], i);
+    }
+
+    #[test]
+    fn overall_test_assertions() {
+        // assume(0 <= x && x < 10); assert(x < 5); assert(x < 5); assert(x != 0);
+        // assert(!(x < 20));
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "assume", "parameters": ["x"], "nodes": [
+                {"key": "assume", "value": {"type": "assume", "condition": {
+                    "left": {"left": 0, "op": "<=", "right": "x"}, "op": "&&",
+                    "right": {"left": "x", "op": "<", "right": 10}}},
+                 "successors": [{"key": "may", "value": -1}]},
+                {"key": "may", "value": {"type": "assert", "condition": {"left": "x", "op": "<", "right": 5}},
+                 "successors": [{"key": "holds", "value": -1}]},
+                {"key": "holds", "value": {"type": "assert", "condition": {"left": "x", "op": "<", "right": 5}},
+                 "successors": [{"key": "excludes", "value": -1}]},
+                {"key": "excludes", "value": {"type": "assert", "condition": {"left": "x", "op": "!=", "right": 0}},
+                 "successors": [{"key": "fails", "value": -1}]},
+                {"key": "fails", "value": {"type": "assert", "condition": {
+                    "op": "!", "operand": {"left": "x", "op": "<", "right": 20}}},
+                 "successors": [{"key": "end", "value": -1}]},
+                {"key": "end", "value": {"type": "other"}, "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let function = &program.functions[0];
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&function.graph, &function.parameters, &[],
+                                              &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(format!("{:?}", states["may"]["x"]), "[0, 10)");
+        assert_eq!(format!("{:?}", states["holds"]["x"]), "[0, 5)");
+        assert_eq!(format!("{:?}", states["fails"]["x"]), "(0, 5)");
+        assert!(!states.contains_key("end"));
+        assert_eq!(kinds(&diagnostics), vec![("may", DiagnosticKind::PossibleAssertionFailure),
+                                             ("excludes", DiagnosticKind::PossibleAssertionFailure),
+                                             ("fails", DiagnosticKind::AssertionFailure),
+                                             ("end", DiagnosticKind::Unreachable)]);
+        assert_eq!(diagnostics[0].message, "assertion may fail: `assert(x < 5);`");
+    }
+
+    #[test]
+    fn overall_test_division_by_zero() {
+        // int x = 0; if (c > 0) { y = 10 / x; dead; } else if (5 % x < 1) { dead; } end;
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "decl", "parameters": ["c"], "nodes": [
+                {"key": "decl", "value": {"type": "variable_declaration",
+                                          "declarations": [{"identifier": "x", "initializer": 0}]},
+                 "successors": [{"key": "if", "value": -1}]},
+                {"key": "if", "value": {"type": "comparison", "left": "c", "op": ">", "right": 0},
+                 "successors": [{"key": "div", "value": 1}, {"key": "rem", "value": 0}]},
+                {"key": "div", "value": {"type": "variable_assignment", "left": "y",
+                                         "right": {"left": 10, "op": "/", "right": "x"}},
+                 "successors": [{"key": "after_div", "value": -1}]},
+                {"key": "after_div", "value": {"type": "other"}, "successors": [{"key": "end", "value": -1}]},
+                {"key": "rem", "value": {"type": "comparison", "left": {"left": 5, "op": "%", "right": "x"},
+                                         "op": "<", "right": 1},
+                 "successors": [{"key": "after_rem", "value": 1}, {"key": "end", "value": 0}]},
+                {"key": "after_rem", "value": {"type": "other"}, "successors": [{"key": "end", "value": -1}]},
+                {"key": "end", "value": {"type": "other"}, "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let function = &program.functions[0];
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&function.graph, &function.parameters, &[],
+                                              &mut Summaries::default(), &mut diagnostics);
+        assert!(!states.contains_key("end"));
+        assert_eq!(kinds(&diagnostics), vec![("rem", DiagnosticKind::DivisionByZero),
+                                             ("div", DiagnosticKind::DivisionByZero),
+                                             ("after_rem", DiagnosticKind::Unreachable),
+                                             ("after_div", DiagnosticKind::Unreachable),
+                                             ("end", DiagnosticKind::Unreachable)]);
+    }
+
+    #[test]
+    fn overall_test_assumptions_are_silent() {
+        // int u; assume(u < 3); assume(10 / x > 1); assume(x < 20 || x > 10);
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "decl", "parameters": ["x"], "nodes": [
+                {"key": "decl", "value": {"type": "variable_declaration",
+                                          "declarations": [{"identifier": "u"}]},
+                 "successors": [{"key": "u", "value": -1}]},
+                {"key": "u", "value": {"type": "assume", "condition": {"left": "u", "op": "<", "right": 3}},
+                 "successors": [{"key": "div", "value": -1}]},
+                {"key": "div", "value": {"type": "assume", "condition": {
+                    "left": {"left": 10, "op": "/", "right": "x"}, "op": ">", "right": 1}},
+                 "successors": [{"key": "or", "value": -1}]},
+                {"key": "or", "value": {"type": "assume", "condition": {
+                    "left": {"left": "x", "op": "<", "right": 20}, "op": "||",
+                    "right": {"left": "x", "op": ">", "right": 10}}},
+                 "successors": [{"key": "end", "value": -1}]},
+                {"key": "end", "value": {"type": "other"}, "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let function = &program.functions[0];
+        let mut diagnostics = Vec::new();
+        let states = numerical_value_analysis(&function.graph, &function.parameters, &[],
+                                              &mut Summaries::default(), &mut diagnostics);
+        assert_eq!(format!("{:?}", states["div"]["u"]), "[-inf, 3)");
+        assert!(diagnostics.is_empty(), "{:?}", kinds(&diagnostics));
+    }
+
+    #[test]
+    fn analyze_invariants() {
+        // x = 3; if (x < y) { return x; }
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "x", "parameters": [{"name": "y", "min": 0, "max": 5}], "nodes": [
+                {"key": "x", "value": {"type": "variable_assignment", "left": "x", "right": 3},
+                 "successors": [{"key": "if", "value": -1}]},
+                {"key": "if", "value": {"type": "comparison", "left": "x", "op": "<", "right": "y"},
+                 "successors": [{"key": "ret", "value": 1}, {"key": "end", "value": 0}]},
+                {"key": "ret", "value": {"type": "return", "value": "x"}, "successors": []},
+                {"key": "end", "value": {"type": "assert", "condition": {"left": "y", "op": ">", "right": 5}},
+                 "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        assert!(analyze(&program, false).functions[0].invariants.is_none());
+        let report = analyze(&program, true);
+        let invariants = report.functions[0].invariants.as_ref().unwrap();
+        assert_eq!(invariants.keys().collect::<Vec<_>>(), vec!["end", "if", "ret", "x"]);
+        assert_eq!(format!("{:?}", invariants["x"].entry), r#"{"y": [0, 5]}"#);
+        assert_eq!(format!("{:?}", invariants["x"].exit), r#"Some({"x": [3, 3], "y": [0, 5]})"#);
+        assert_eq!(format!("{:?}", invariants["ret"].entry), r#"{"x": [3, 3], "y": (3, 5]}"#);
+        assert_eq!(format!("{:?}", invariants["ret"].exit), r#"Some({"x": [3, 3], "y": (3, 5]})"#);
+        assert!(invariants["end"].exit.is_none());
+        assert_eq!(serde_json::to_string(&invariants["if"]).unwrap(),
+                   r#"{"entry":{"x":"[3, 3]","y":"[0, 5]"},"exit":{"x":"[3, 3]","y":"[0, 5]"}}"#);
+    }
+
+    #[test]
+    fn analyze_invariants_arrays() {
+        // u8 buf[n]; buf[0] = 7; x = buf[0];
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "decl", "parameters": [{"name": "n", "min": 1, "max": 10}], "nodes": [
+                {"key": "decl", "value": {"type": "variable_declaration", "declarations": [
+                    {"identifier": "buf", "type": "u8", "length": "n"}]},
+                 "successors": [{"key": "store", "value": -1}]},
+                {"key": "store", "value": {"type": "variable_assignment",
+                                           "left": {"array": "buf", "index": 0}, "right": 7},
+                 "successors": [{"key": "x", "value": -1}]},
+                {"key": "x", "value": {"type": "variable_assignment", "left": "x",
+                                       "right": {"array": "buf", "index": 0}},
+                 "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let report = analyze(&program, true);
+        let invariants = report.functions[0].invariants.as_ref().unwrap();
+        assert_eq!(format!("{:?}", invariants["store"].entry), r#"{"n": [1, 10]}"#);
+        assert_eq!(format!("{:?}", invariants["x"].exit), r#"Some({"n": [1, 10], "x": [0, 255]})"#);
+    }
+
+    #[test]
+    fn analyze_equal_ranges() {
+        // int f(x, y) { if (x == y) return 1; else return 2; }
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "if",
+             "parameters": [{"name": "x", "min": 0, "max": 10}, {"name": "y", "min": 0, "max": 10}],
+             "nodes": [
+                {"key": "if", "value": {"type": "comparison", "left": "x", "op": "==", "right": "y"},
+                 "successors": [{"key": "one", "value": 1}, {"key": "two", "value": 0}]},
+                {"key": "one", "value": {"type": "return", "value": 1}, "successors": []},
+                {"key": "two", "value": {"type": "return", "value": 2}, "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let report = analyze(&program, false);
+        assert!(report.diagnostics.is_empty());
+        assert_eq!(format!("{:?}", report.functions[0].returns.as_ref().unwrap()), "[1, 1] U [2, 2]");
+    }
+
+    #[test]
+    fn analyze_remainder_divisor() {
+        // int f(a) { if (32 % a < 40) return a; else return 0; }
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "if", "parameters": ["a"], "nodes": [
+                {"key": "if", "value": {"type": "comparison",
+                                        "left": {"left": 32, "op": "%", "right": "a"}, "op": "<", "right": 40},
+                 "successors": [{"key": "a", "value": 1}, {"key": "zero", "value": 0}]},
+                {"key": "a", "value": {"type": "return", "value": "a"}, "successors": []},
+                {"key": "zero", "value": {"type": "return", "value": 0}, "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        let report = analyze(&program, false);
+        // a = 100 returns 100
+        assert_eq!(format!("{:?}", report.functions[0].returns.as_ref().unwrap()),
+                   "[-inf, 0) U (0, inf]");
     }
 }
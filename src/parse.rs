@@ -2,16 +2,51 @@ extern crate serde;
 extern crate serde_json;
 
 use std::fs::File;
-use std::io;
-use std::io::prelude::*;
-use std::collections::HashMap;
+use std::io::{self, Read};
+use std::collections::{hash_map, HashMap, HashSet};
 use std::fmt;
+use std::error;
 
+/// The whole file, either a list of functions or, on its own, the graph of
+/// a single function called `main` starting at the first node.
+enum Input {
+    Functions(Vec<FunctionInput>),
+    Nodes(Vec<Node>),
+}
+#[derive(Deserialize, Debug, Clone)]
+struct FunctionsInput {
+    functions: Vec<FunctionInput>,
+}
+#[derive(Deserialize, Debug, Clone)]
+struct NodesInput {
+    nodes: Vec<Node>,
+}
 #[derive(Deserialize, Debug, Clone)]
-struct Nodes {
+struct FunctionInput {
+    name: String,
+    entry: String,
+    #[serde(default)]
+    parameters: Vec<ParameterInput>,
     nodes: Vec<Node>,
 }
 #[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum ParameterInput {
+    Name(String),
+    Declared(Parameter),
+}
+/// A parameter and what is known about its argument.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: String,
+    #[serde(default)]
+    pub min: Option<i64>,
+    #[serde(default)]
+    pub max: Option<i64>,
+    #[serde(rename = "type", default)]
+    pub integer_type: Option<IntegerType>,
+}
+#[derive(Deserialize, Debug, Clone)]
 struct Node {
     key: String,
     value: NodeValue,
@@ -22,19 +57,76 @@ struct Node {
 #[serde(rename_all = "snake_case")]
 pub enum NodeValue {
     VariableDeclaration { declarations: Vec<Declaration> },
-    VariableAssignment { left: /*Expression, op:*/ String, right: Expression },
+    VariableAssignment {
+        /// An `Identifier` or an `Index`.
+        left: Expression,
+        #[serde(default = "assignment_op")]
+        op: String,
+        right: Expression,
+    },
     Comparison { left: Expression, op: String, right: Expression },
+    /// `++` or `--`.
+    Increment {
+        identifier: String,
+        op: String,
+        #[serde(default)]
+        prefix: bool,
+    },
+    /// A call whose result isn't used.
+    Call { function: String, arguments: Vec<Expression> },
+    Return { value: Expression },
+    /// Reported if `condition` may fail.  Execution continues as if it passed.
+    Assert { condition: Expression },
+    /// Like `Assert`, but trusted without being checked.
+    Assume { condition: Expression },
     Other,
 }
+fn assignment_op() -> String {
+    "=".to_string()
+}
 #[derive(Deserialize, Debug, Clone)]
 pub struct Declaration {
     pub identifier: String,
-    pub initializer: Expression,
+    /// `None` if the variable is left uninitialized.
+    #[serde(default)]
+    pub initializer: Option<Expression>,
+    #[serde(rename = "type", default)]
+    pub integer_type: Option<IntegerType>,
+    /// `Some` if this is an array, whose elements are all `integer_type`.
+    #[serde(default)]
+    pub length: Option<Expression>,
+}
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IntegerType {
+    I8, I16, I32, I64,
+    U8, U16, U32, U64,
+}
+impl IntegerType {
+    /// The smallest and largest value of this type, where `None` is too big
+    /// for an i64.
+    pub fn bounds(self) -> (i64, Option<i64>) {
+        use self::IntegerType::*;
+        match self {
+            I8 => (i8::MIN.into(), Some(i8::MAX.into())),
+            I16 => (i16::MIN.into(), Some(i16::MAX.into())),
+            I32 => (i32::MIN.into(), Some(i32::MAX.into())),
+            I64 => (i64::MIN, Some(i64::MAX)),
+            U8 => (0, Some(u8::MAX.into())),
+            U16 => (0, Some(u16::MAX.into())),
+            U32 => (0, Some(u32::MAX.into())),
+            U64 => (0, None),
+        }
+    }
 }
 #[derive(Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum Expression {
     Binary { left: Box<Expression>, op: String, right: Box<Expression> },
+    Unary { op: String, operand: Box<Expression> },
+    Call { function: String, arguments: Vec<Expression> },
+    /// An element of an array.
+    Index { array: String, index: Box<Expression> },
     Number(i64),
     Identifier(String),
     Other,
@@ -45,6 +137,15 @@ pub struct Successor {
     pub value: i64,
 }
 
+pub struct Program {
+    pub functions: Vec<Function>,
+}
+pub struct Function {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub graph: Graph,
+}
+
 pub struct Graph {
     values: HashMap<String, NodeValue>,
     successors: HashMap<String, Vec<Successor>>,
@@ -67,6 +168,51 @@ impl Graph {
     pub fn first(&self) -> &String {
         &self.first
     }
+
+    pub fn nodes(&self) -> hash_map::Iter<'_, String, NodeValue> {
+        self.values.iter()
+    }
+
+    /// Keys reachable from `first`, in reverse postorder of a depth first
+    /// search.  Every node comes before its successors except along back
+    /// edges, which makes this the natural priority for a worklist.
+    pub fn reverse_postorder(&self) -> Vec<&str> {
+        self.depth_first().0
+    }
+
+    /// Targets of the back edges found by the depth first search from
+    /// `first`.  Every cycle in the graph passes through one of these.
+    pub fn loop_heads(&self) -> HashSet<&str> {
+        self.depth_first().1
+    }
+
+    fn depth_first(&self) -> (Vec<&str>, HashSet<&str>) {
+        let mut visited = HashSet::new();
+        let mut on_stack = HashSet::new();
+        let mut loop_heads = HashSet::new();
+        let mut order = Vec::new();
+        let mut stack = vec![(self.first.as_str(), 0)];
+        visited.insert(self.first.as_str());
+        on_stack.insert(self.first.as_str());
+        while let Some((key, index)) = stack.pop() {
+            let successors = self.successors_of(key).unwrap();
+            if index < successors.len() {
+                stack.push((key, index + 1));
+                let successor = successors[index].key.as_str();
+                if visited.insert(successor) {
+                    on_stack.insert(successor);
+                    stack.push((successor, 0));
+                } else if on_stack.contains(successor) {
+                    loop_heads.insert(successor);
+                }
+            } else {
+                on_stack.remove(key);
+                order.push(key);
+            }
+        }
+        order.reverse();
+        (order, loop_heads)
+    }
 }
 impl fmt::Debug for Graph {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -91,10 +237,24 @@ impl fmt::Display for NodeValue {
                 }
                 Ok(())
             },
-            VariableAssignment { left, right } =>
-                write!(f, "{} = {};", left, right),
+            VariableAssignment { left, op, right } =>
+                write!(f, "{} {} {};", left, op, right),
             Comparison { left, op, right } =>
                 write!(f, "{} {} {}", left, op, right),
+            Increment { identifier, op, prefix: true } =>
+                write!(f, "{}{};", op, identifier),
+            Increment { identifier, op, prefix: false } =>
+                write!(f, "{}{};", identifier, op),
+            Call { function, arguments } => {
+                write_call(f, function, arguments)?;
+                write!(f, ";")
+            },
+            Return { value } =>
+                write!(f, "return {};", value),
+            Assert { condition } =>
+                write!(f, "assert({});", condition),
+            Assume { condition } =>
+                write!(f, "assume({});", condition),
             Other =>
                 write!(f, "__other_stmt();"),
         }
@@ -103,7 +263,27 @@ impl fmt::Display for NodeValue {
 
 impl fmt::Display for Declaration {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "let {} = {}", self.identifier, self.initializer)
+        match self.integer_type {
+            Some(integer_type) => write!(f, "{} {}", integer_type, self.identifier)?,
+            None => write!(f, "let {}", self.identifier)?,
+        }
+        if let Some(length) = &self.length {
+            write!(f, "[{}]", length)?;
+        }
+        match &self.initializer {
+            Some(initializer) => write!(f, " = {}", initializer),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for IntegerType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use IntegerType::*;
+        write!(f, "{}", match self {
+            I8 => "i8", I16 => "i16", I32 => "i32", I64 => "i64",
+            U8 => "u8", U16 => "u16", U32 => "u32", U64 => "u64",
+        })
     }
 }
 
@@ -113,6 +293,14 @@ impl fmt::Display for Expression {
         match self {
             Binary { left, op, right } =>
                 write!(f, "{} {} {}", left, op, right),
+            Unary { op, operand } => match **operand {
+                Binary { .. } => write!(f, "{}({})", op, operand),
+                _ => write!(f, "{}{}", op, operand),
+            },
+            Call { function, arguments } =>
+                write_call(f, function, arguments),
+            Index { array, index } =>
+                write!(f, "{}[{}]", array, index),
             Number(num) =>
                 write!(f, "{}", num),
             Identifier(identifier) =>
@@ -123,21 +311,466 @@ impl fmt::Display for Expression {
     }
 }
 
-pub fn parse(path: &str) -> io::Result<Graph> {
-    Ok(parse_(serde_json::from_reader(File::open(path)?)?))
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// There are no nodes.
+    EmptyGraph,
+    /// Two functions have this name.
+    DuplicateFunction(String),
+    /// The entry of a function isn't one of its nodes.
+    UnknownEntry(String),
+    /// A parameter whose `min` is greater than its `max`.
+    EmptyParameter(String),
+    /// A parameter whose `min` and `max` allow no value of its type.
+    ParameterOutsideType(String),
+    /// Two nodes have this key.
+    DuplicateKey(String),
+    /// `key` has `successor` as a successor but there is no such node.
+    UnknownSuccessor { key: String, successor: String },
+    /// A comparison without both a pass (1) and a fail (0) successor, or
+    /// with a successor that is neither.
+    MissingBranch(String),
+    /// A node other than a comparison with a pass or fail successor.
+    UnexpectedBranch(String),
+    /// A return, which ends the function, with successors.
+    ReturnWithSuccessors(String),
+    /// An assignment or increment whose `op` isn't one the language has.
+    UnknownOperator { key: String, op: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ParseError::*;
+        match self {
+            Io(e) => write!(f, "{}", e),
+            Json(e) => write!(f, "{}", e),
+            EmptyGraph => write!(f, "the graph has no nodes"),
+            DuplicateFunction(name) => write!(f, "duplicate function {}", name),
+            UnknownEntry(key) => write!(f, "unknown entry node {}", key),
+            EmptyParameter(name) => write!(f, "parameter {} has min greater than max", name),
+            ParameterOutsideType(name) =>
+                write!(f, "parameter {} has no value its type can hold", name),
+            DuplicateKey(key) => write!(f, "duplicate node {}", key),
+            UnknownSuccessor { key, successor } =>
+                write!(f, "node {} has unknown successor {}", key, successor),
+            MissingBranch(key) =>
+                write!(f, "comparison {} needs exactly a pass (1) and a fail (0) successor", key),
+            UnexpectedBranch(key) =>
+                write!(f, "node {} is not a comparison but has a pass or fail successor", key),
+            ReturnWithSuccessors(key) => write!(f, "return {} has successors", key),
+            UnknownOperator { key, op } => write!(f, "node {} has unknown operator {}", key, op),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ParseError {
+    fn from(e: serde_json::Error) -> Self {
+        ParseError::Json(e)
+    }
+}
+
+impl From<ParseError> for io::Error {
+    fn from(e: ParseError) -> Self {
+        match e {
+            ParseError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
 }
 
-pub fn parse_contents(contents: String) -> io::Result<Graph> {
-    Ok(parse_(serde_json::from_str(&contents)?))
+fn write_call(f: &mut fmt::Formatter, function: &str, arguments: &[Expression]) -> fmt::Result {
+    write!(f, "{}(", function)?;
+    for (i, argument) in arguments.iter().enumerate() {
+        if i != 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", argument)?;
+    }
+    write!(f, ")")
+}
+
+pub fn parse(path: &str) -> Result<Program, ParseError> {
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+    parse_contents(contents)
+}
+
+pub fn parse_contents(contents: String) -> Result<Program, ParseError> {
+    parse_(read_input(&contents)?)
+}
+
+/// Picks the shape of the file before reading it as that shape, so errors
+/// say what is wrong and where rather than that neither shape matched.
+fn read_input(contents: &str) -> Result<Input, ParseError> {
+    let value: serde_json::Value = serde_json::from_str(contents)?;
+    if value.get("functions").is_some() {
+        let input: FunctionsInput = serde_json::from_str(contents)?;
+        Ok(Input::Functions(input.functions))
+    } else {
+        let input: NodesInput = serde_json::from_str(contents)?;
+        Ok(Input::Nodes(input.nodes))
+    }
+}
+
+fn parse_(input: Input) -> Result<Program, ParseError> {
+    let inputs = match input {
+        Input::Functions(functions) => functions,
+        Input::Nodes(nodes) => {
+            let entry = match nodes.first() {
+                Some(node) => node.key.clone(),
+                None => return Err(ParseError::EmptyGraph),
+            };
+            vec![FunctionInput { name: "main".to_string(), entry, parameters: Vec::new(), nodes }]
+        },
+    };
+    let mut names = HashSet::new();
+    let mut functions = Vec::new();
+    for input in inputs {
+        if !names.insert(input.name.clone()) {
+            return Err(ParseError::DuplicateFunction(input.name));
+        }
+        let mut parameters = Vec::new();
+        for parameter in input.parameters {
+            let parameter = match parameter {
+                ParameterInput::Name(name) =>
+                    Parameter { name, min: None, max: None, integer_type: None },
+                ParameterInput::Declared(parameter) => parameter,
+            };
+            if let (Some(min), Some(max)) = (parameter.min, parameter.max) {
+                if min > max {
+                    return Err(ParseError::EmptyParameter(parameter.name));
+                }
+            }
+            if let Some(integer_type) = parameter.integer_type {
+                let (type_min, type_max) = integer_type.bounds();
+                let below = parameter.max.is_some_and(|max| max < type_min);
+                let above = match (parameter.min, type_max) {
+                    (Some(min), Some(type_max)) => min > type_max,
+                    _ => false,
+                };
+                if below || above {
+                    return Err(ParseError::ParameterOutsideType(parameter.name));
+                }
+            }
+            parameters.push(parameter);
+        }
+        functions.push(Function {
+            name: input.name,
+            parameters,
+            graph: parse_graph(input.nodes, input.entry)?,
+        });
+    }
+    Ok(Program { functions })
 }
 
-fn parse_(nodes: Nodes) -> Graph {
+fn parse_graph(nodes: Vec<Node>, first: String) -> Result<Graph, ParseError> {
     let mut values = HashMap::new();
     let mut successors = HashMap::new();
-    let first = nodes.nodes[0].key.clone();
-    for node in nodes.nodes.into_iter() {
+    if nodes.is_empty() {
+        return Err(ParseError::EmptyGraph);
+    }
+    for node in nodes.into_iter() {
+        if values.contains_key(&node.key) {
+            return Err(ParseError::DuplicateKey(node.key));
+        }
         values.insert(node.key.clone(), node.value);
         successors.insert(node.key, node.successors);
     }
-    Graph { values, successors, first }
+    if !values.contains_key(&first) {
+        return Err(ParseError::UnknownEntry(first));
+    }
+    let graph = Graph { values, successors, first };
+    validate(&graph)?;
+    Ok(graph)
+}
+
+/// Checks that every successor exists and that only comparisons branch.
+fn validate(graph: &Graph) -> Result<(), ParseError> {
+    // Sorted so the same error is reported every time
+    let mut keys: Vec<&String> = graph.values.keys().collect();
+    keys.sort();
+    for key in keys {
+        let successors = &graph.successors[key];
+        for succ in successors {
+            if !graph.values.contains_key(&succ.key) {
+                return Err(ParseError::UnknownSuccessor {
+                    key: key.clone(),
+                    successor: succ.key.clone(),
+                });
+            }
+        }
+        if let NodeValue::Comparison { .. } = graph.values[key] {
+            let has = |value| successors.iter().any(|succ| succ.value == value);
+            if !has(1) || !has(0) || successors.iter().any(|succ| succ.value != 1 && succ.value != 0) {
+                return Err(ParseError::MissingBranch(key.clone()));
+            }
+        } else if successors.iter().any(|succ| succ.value != -1) {
+            return Err(ParseError::UnexpectedBranch(key.clone()));
+        }
+        if let NodeValue::Return { .. } = graph.values[key] {
+            if !successors.is_empty() {
+                return Err(ParseError::ReturnWithSuccessors(key.clone()));
+            }
+        }
+        let unknown_op = match &graph.values[key] {
+            NodeValue::VariableAssignment { op, .. } if !ASSIGNMENT_OPS.contains(&&**op) => Some(op),
+            NodeValue::Increment { op, .. } if op != "++" && op != "--" => Some(op),
+            _ => None,
+        };
+        if let Some(op) = unknown_op {
+            return Err(ParseError::UnknownOperator { key: key.clone(), op: op.clone() });
+        }
+    }
+    Ok(())
+}
+
+const ASSIGNMENT_OPS: [&str; 11] = ["=", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<=", ">>="];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_nodes(nodes: &str) -> Result<Graph, ParseError> {
+        parse_contents(format!("{{\"nodes\": [{}]}}", nodes))
+            .map(|mut program| program.functions.remove(0).graph)
+    }
+
+    #[test]
+    fn parse_valid() {
+        let graph = parse_nodes(r#"
+            {"key": "a", "value": {"type": "comparison", "left": "x", "op": "<", "right": 3},
+             "successors": [{"key": "b", "value": 1}, {"key": "c", "value": 0}]},
+            {"key": "b", "value": {"type": "other"}, "successors": [{"key": "c", "value": -1}]},
+            {"key": "c", "value": {"type": "other"}, "successors": []}"#).unwrap();
+        assert_eq!(graph.first(), "a");
+        assert_eq!(graph.reverse_postorder(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn parse_unknown_node_type() {
+        match parse_nodes(r#"{"key": "a", "value": {"type": "comparisn"}, "successors": []}"#) {
+            Err(ParseError::Json(e)) => {
+                let message = e.to_string();
+                assert!(message.contains("unknown variant `comparisn`"), "{}", message);
+                assert_eq!(e.line(), 1);
+            },
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+        match parse_contents(r#"{"functions": [{"name": "f", "entry": "a", "nodes": [
+            {"key": "a", "value": {"type": "comparisn"}, "successors": []}]}]}"#.to_string()) {
+            Err(ParseError::Json(e)) => {
+                let message = e.to_string();
+                assert!(message.contains("unknown variant `comparisn`"), "{}", message);
+                assert_eq!(e.line(), 2);
+            },
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_empty() {
+        match parse_nodes("") {
+            Err(ParseError::EmptyGraph) => {},
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_invalid_json() {
+        match parse_contents("{".to_string()) {
+            Err(ParseError::Json(_)) => {},
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_duplicate_key() {
+        match parse_nodes(r#"
+            {"key": "a", "value": {"type": "other"}, "successors": []},
+            {"key": "a", "value": {"type": "other"}, "successors": []}"#) {
+            Err(ParseError::DuplicateKey(key)) => assert_eq!(key, "a"),
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_unknown_successor() {
+        match parse_nodes(r#"
+            {"key": "a", "value": {"type": "other"}, "successors": [{"key": "b", "value": -1}]}"#) {
+            Err(ParseError::UnknownSuccessor { key, successor }) => {
+                assert_eq!(key, "a");
+                assert_eq!(successor, "b");
+            },
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_missing_branch() {
+        match parse_nodes(r#"
+            {"key": "a", "value": {"type": "comparison", "left": "x", "op": "<", "right": 3},
+             "successors": [{"key": "b", "value": 1}, {"key": "b", "value": -1}]},
+            {"key": "b", "value": {"type": "other"}, "successors": []}"#) {
+            Err(ParseError::MissingBranch(key)) => assert_eq!(key, "a"),
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_unexpected_branch() {
+        match parse_nodes(r#"
+            {"key": "a", "value": {"type": "other"}, "successors": [{"key": "b", "value": 1}]},
+            {"key": "b", "value": {"type": "other"}, "successors": []}"#) {
+            Err(ParseError::UnexpectedBranch(key)) => assert_eq!(key, "a"),
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_return_with_successors() {
+        match parse_nodes(r#"
+            {"key": "a", "value": {"type": "return", "value": 3},
+             "successors": [{"key": "b", "value": -1}]},
+            {"key": "b", "value": {"type": "other"}, "successors": []}"#) {
+            Err(ParseError::ReturnWithSuccessors(key)) => assert_eq!(key, "a"),
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_unknown_operator() {
+        match parse_nodes(r#"
+            {"key": "a", "value": {"type": "variable_assignment", "left": "x", "op": "==", "right": 3},
+             "successors": []}"#) {
+            Err(ParseError::UnknownOperator { key, op }) => {
+                assert_eq!(key, "a");
+                assert_eq!(op, "==");
+            },
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+        match parse_nodes(r#"
+            {"key": "a", "value": {"type": "increment", "identifier": "x", "op": "**", "prefix": true},
+             "successors": []}"#) {
+            Err(ParseError::UnknownOperator { key, op }) => {
+                assert_eq!(key, "a");
+                assert_eq!(op, "**");
+            },
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_functions() {
+        let program = parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "b",
+             "parameters": ["x", {"name": "n", "min": 0, "max": 100}, {"name": "c", "type": "u8"}],
+             "nodes": [
+                {"key": "a", "value": {"type": "other"}, "successors": []},
+                {"key": "b", "value": {"type": "other"}, "successors": [{"key": "a", "value": -1}]}
+            ]},
+            {"name": "g", "entry": "a", "nodes": [
+                {"key": "a", "value": {"type": "other"}, "successors": []}
+            ]}
+        ]}"#.to_string()).unwrap();
+        assert_eq!(program.functions.len(), 2);
+        assert_eq!(program.functions[0].name, "f");
+        assert_eq!(program.functions[0].parameters, vec![
+            Parameter { name: "x".to_string(), min: None, max: None, integer_type: None },
+            Parameter { name: "n".to_string(), min: Some(0), max: Some(100), integer_type: None },
+            Parameter { name: "c".to_string(), min: None, max: None,
+                        integer_type: Some(IntegerType::U8) },
+        ]);
+        assert_eq!(program.functions[0].graph.first(), "b");
+        assert_eq!(program.functions[1].name, "g");
+        assert!(program.functions[1].parameters.is_empty());
+    }
+
+    #[test]
+    fn parse_legacy_nodes() {
+        let program = parse_contents(r#"{"nodes": [
+            {"key": "a", "value": {"type": "other"}, "successors": []}
+        ]}"#.to_string()).unwrap();
+        assert_eq!(program.functions.len(), 1);
+        assert_eq!(program.functions[0].name, "main");
+        assert_eq!(program.functions[0].graph.first(), "a");
+    }
+
+    #[test]
+    fn parse_duplicate_function() {
+        match parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "a", "nodes": [{"key": "a", "value": {"type": "other"}, "successors": []}]},
+            {"name": "f", "entry": "a", "nodes": [{"key": "a", "value": {"type": "other"}, "successors": []}]}
+        ]}"#.to_string()) {
+            Err(ParseError::DuplicateFunction(name)) => assert_eq!(name, "f"),
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_unknown_entry() {
+        match parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "b", "nodes": [{"key": "a", "value": {"type": "other"}, "successors": []}]}
+        ]}"#.to_string()) {
+            Err(ParseError::UnknownEntry(key)) => assert_eq!(key, "b"),
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_empty_parameter() {
+        match parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "a", "parameters": [{"name": "n", "min": 3, "max": 2}],
+             "nodes": [{"key": "a", "value": {"type": "other"}, "successors": []}]}
+        ]}"#.to_string()) {
+            Err(ParseError::EmptyParameter(name)) => assert_eq!(name, "n"),
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_parameter_outside_type() {
+        match parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "a", "parameters": [{"name": "n", "min": 300, "max": 400, "type": "u8"}],
+             "nodes": [{"key": "a", "value": {"type": "other"}, "successors": []}]}
+        ]}"#.to_string()) {
+            Err(ParseError::ParameterOutsideType(name)) => assert_eq!(name, "n"),
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+        match parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "a", "parameters": [{"name": "n", "max": -1, "type": "u64"}],
+             "nodes": [{"key": "a", "value": {"type": "other"}, "successors": []}]}
+        ]}"#.to_string()) {
+            Err(ParseError::ParameterOutsideType(name)) => assert_eq!(name, "n"),
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+        // Only part of the range is outside the type
+        assert!(parse_contents(r#"{"functions": [
+            {"name": "f", "entry": "a", "parameters": [{"name": "n", "min": 200, "max": 400, "type": "u8"}],
+             "nodes": [{"key": "a", "value": {"type": "other"}, "successors": []}]}
+        ]}"#.to_string()).is_ok());
+    }
+
+    #[test]
+    fn parse_arrays() {
+        let graph = parse_nodes(r#"
+            {"key": "a", "value": {"type": "variable_declaration", "declarations": [
+                {"identifier": "buf", "type": "u8", "length": {"left": "n", "op": "+", "right": 1}}]},
+             "successors": [{"key": "b", "value": -1}]},
+            {"key": "b", "value": {"type": "variable_assignment",
+                                   "left": {"array": "buf", "index": "i"},
+                                   "right": {"array": "buf", "index": {"left": "i", "op": "-", "right": 1}}},
+             "successors": []}"#).unwrap();
+        assert_eq!(graph.value_of("a").unwrap().to_string(), "u8 buf[n + 1];");
+        assert_eq!(graph.value_of("b").unwrap().to_string(), "buf[i] = buf[i - 1];");
+    }
 }